@@ -18,7 +18,7 @@ use exonum_cli::{NodeBuilder, Spec};
 async fn main() -> anyhow::Result<()> {
     exonum::helpers::init_logger()?;
     NodeBuilder::new()
-        .with(Spec::new(exonum_btc_anchoring::BtcAnchoringService))
+        .with(Spec::migrating(exonum_btc_anchoring::BtcAnchoringService))
         .run()
         .await
 }
@@ -0,0 +1,93 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{anyhow, bail, ensure};
+use exonum::{
+    merkledb::{Database, DbOptions, RocksDB},
+    runtime::SnapshotExt,
+};
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+
+use exonum_btc_anchoring::{blockchain::Schema, btc};
+
+/// BTC anchoring chain checker
+///
+/// Checks the consistency of the anchoring transactions chain stored in the node
+/// database. The node should be stopped during the check, since its database
+/// cannot be opened by several processes at once.
+#[derive(StructOpt)]
+struct Opts {
+    /// Path to the node database directory.
+    #[structopt(long, short = "d")]
+    db_path: PathBuf,
+    /// Anchoring service instance name.
+    #[structopt(long, short = "i", default_value = "anchoring")]
+    instance_name: String,
+}
+
+fn main() -> anyhow::Result<()> {
+    let opts = Opts::from_args();
+    ensure!(
+        opts.db_path.exists(),
+        "Database directory {} does not exist",
+        opts.db_path.display()
+    );
+
+    // RocksDB bindings used by the node support neither the read-only nor the secondary
+    // mode, and a checkpoint can only be created by the process owning the database.
+    // So the database is opened as is, and the lock held by a running node is reported.
+    // The chain is only read, the database content is not modified.
+    let db = RocksDB::open(&opts.db_path, &DbOptions::default()).map_err(|e| {
+        if e.to_string().to_lowercase().contains("lock") {
+            anyhow!(
+                "Database {} is locked, probably by a running node. \
+                 Stop the node before checking the anchoring chain. {}",
+                opts.db_path.display(),
+                e
+            )
+        } else {
+            anyhow!("Unable to open the node database: {}", e)
+        }
+    })?;
+    let snapshot = db.snapshot();
+    let access = snapshot
+        .for_service(opts.instance_name.as_str())
+        .ok_or_else(|| {
+            anyhow!(
+                "Service instance `{}` is not found in the database",
+                opts.instance_name
+            )
+        })?;
+    let schema = Schema::new(access);
+
+    let result = btc::verify_anchoring_chain(schema.transactions_chain.iter(), |txid| {
+        schema.spent_funding_transaction(txid)
+    });
+    if let Err(errors) = result {
+        for e in &errors {
+            eprintln!("{}", e);
+        }
+        bail!(
+            "Anchoring chain is inconsistent, {} problems found",
+            errors.len()
+        );
+    }
+    println!(
+        "Anchoring chain of {} transactions is consistent",
+        schema.transactions_chain.len()
+    );
+    Ok(())
+}
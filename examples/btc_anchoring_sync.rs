@@ -12,31 +12,47 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::{anyhow, bail};
+use anyhow::{anyhow, bail, ensure};
 use async_trait::async_trait;
 use bitcoincore_rpc::{Auth as BitcoinRpcAuth, Client as BitcoinRpcClient};
 use exonum::crypto::Hash;
 use exonum_btc_anchoring::{
-    api::{AnchoringChainLength, AnchoringProposalState, IndexQuery, PrivateApi},
+    api::{
+        AnchoringChainLength, AnchoringProposalState, AnchoringTransactionSummary, IndexQuery,
+        PrivateApi, ProposalSighashes, SignedInputsQuery, TransactionsPageQuery,
+    },
     blockchain::SignInput,
     btc,
     config::Config as AnchoringConfig,
-    sync::{AnchoringChainUpdateTask, ChainUpdateError, SyncWithBitcoinError, SyncWithBitcoinTask},
+    sync::{
+        self, AnchoringChainUpdateTask, BitcoinNodeInfo, BitcoinRelay, ChainUpdateError,
+        ElectrumError, ElectrumRelay, FailoverRelay, RelayError, RetryRelay, SyncWithBitcoinError,
+        SyncWithBitcoinTask, TransactionStatus,
+    },
 };
+use jsonrpc::Error as JsonRpcError;
 use serde::{de::DeserializeOwned, ser::Serialize};
 use serde_derive::{Deserialize, Serialize};
 use structopt::StructOpt;
 use tokio::time::delay_for;
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     convert::TryFrom,
-    fs::{self, File},
+    fmt::Display,
+    fs::{self, File, OpenOptions},
     io::prelude::*,
+    num::NonZeroU64,
     path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
     time::Duration,
 };
 
+/// Minimal supported version of the Bitcoin node in the `getnetworkinfo` format.
+const MIN_BITCOIND_VERSION: u64 = 170_000;
+/// Number of the sync loop iterations between the Bitcoin node health checks.
+const HEALTH_CHECK_INTERVAL: u64 = 120;
+
 /// Client implementation for the API of the anchoring service instance.
 #[derive(Debug, Clone)]
 pub struct ApiClient {
@@ -103,6 +119,19 @@ impl ApiClient {
             .json()
             .await
     }
+
+    /// Returns summaries of the anchoring transactions via the public API.
+    async fn transactions_summary(
+        &self,
+        from: u64,
+        count: u64,
+    ) -> Result<Vec<AnchoringTransactionSummary>, reqwest::Error> {
+        self.get_query(
+            "transactions-summary",
+            &TransactionsPageQuery { from, count },
+        )
+        .await
+    }
 }
 
 #[async_trait]
@@ -121,6 +150,22 @@ impl PrivateApi for ApiClient {
         self.get("anchoring-proposal").await
     }
 
+    async fn anchoring_proposal_sighashes(&self) -> Result<Option<ProposalSighashes>, Self::Error> {
+        self.get("anchoring-proposal/sighashes").await
+    }
+
+    async fn signed_inputs(
+        &self,
+        txid: btc::Sha256d,
+        bitcoin_key: btc::PublicKey,
+    ) -> Result<Vec<u32>, Self::Error> {
+        self.get_query(
+            "anchoring-proposal/signed-inputs",
+            &SignedInputsQuery { txid, bitcoin_key },
+        )
+        .await
+    }
+
     async fn config(&self) -> Result<AnchoringConfig, Self::Error> {
         self.get("config").await
     }
@@ -162,6 +207,18 @@ struct GenerateConfigCommand {
     /// Bitcoin RPC password.
     #[structopt(long)]
     bitcoin_rpc_password: Option<String>,
+    /// Path to the Bitcoin RPC cookie file, which is used instead of the username
+    /// and password.
+    #[structopt(long, conflicts_with_all = &["bitcoin-rpc-user", "bitcoin-rpc-password"])]
+    bitcoin_rpc_cookie_file: Option<PathBuf>,
+    /// Electrum server address in form `{host}:{port}`, which is used instead
+    /// of the Bitcoin RPC.
+    #[structopt(long, conflicts_with = "bitcoin-rpc-host")]
+    electrum_server: Option<String>,
+    /// Derive Bitcoin keys from a newly generated extended private key instead
+    /// of generating each of them independently.
+    #[structopt(long)]
+    extended_key: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -169,6 +226,11 @@ struct RunCommand {
     /// Path to a sync utility configuration file.
     #[structopt(long, short = "c")]
     config: PathBuf,
+    /// Write anchoring transactions to the specified journal file instead of
+    /// broadcasting them to the Bitcoin network. Requires a Bitcoin relay
+    /// in the sync utility config.
+    #[structopt(long)]
+    dry_run: Option<PathBuf>,
 }
 
 /// Generates a new Bitcoin key pair and add them to the key pool of the specified
@@ -180,6 +242,24 @@ struct GenerateKeypairCommand {
     config: PathBuf,
 }
 
+/// Prints summaries of the anchoring transactions with the numbers of their
+/// Bitcoin confirmations.
+#[derive(Debug, StructOpt)]
+struct TransactionsSummaryCommand {
+    /// Path to a sync utility configuration file.
+    #[structopt(long, short = "c")]
+    config: PathBuf,
+    /// Exonum node public API address in form `{http|https}://{address}:{port}`.
+    #[structopt(long)]
+    exonum_public_api: String,
+    /// Index of the first anchoring transaction.
+    #[structopt(long, default_value = "0")]
+    from: u64,
+    /// Maximum number of anchoring transactions.
+    #[structopt(long, default_value = "100")]
+    count: u64,
+}
+
 #[derive(Debug, StructOpt)]
 enum Commands {
     /// Generate initial configuration for the btc anchoring sync utility.
@@ -189,6 +269,9 @@ enum Commands {
     /// Generate a new Bitcoin key pair and add them to the key pool of the specified
     /// configuration file.
     GenerateKeypair(GenerateKeypairCommand),
+    /// Print summaries of the anchoring transactions with the numbers of their
+    /// Bitcoin confirmations.
+    TransactionsSummary(TransactionsSummaryCommand),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -197,7 +280,30 @@ struct SyncConfig {
     instance_name: String,
     #[serde(with = "flatten_keypairs")]
     bitcoin_key_pool: HashMap<btc::PublicKey, btc::PrivateKey>,
+    /// Extended private key, from which the additional Bitcoin keys are derived.
+    #[serde(default)]
+    bitcoin_extended_key: Option<btc::ExtendedPrivateKey>,
+    /// Number of Bitcoin keys derived from the extended private key.
+    #[serde(default)]
+    bitcoin_derived_keys: u32,
+    /// Maximum number of the latest anchoring transactions that will be checked
+    /// while searching for the first uncommitted one. Zero is rejected.
+    #[serde(default)]
+    max_scan_depth: Option<NonZeroU64>,
+    /// Balance threshold in satoshis, below which the utility warns about low funds
+    /// in the anchoring wallet.
+    #[serde(default)]
+    low_funds_threshold: Option<u64>,
+    /// Electrum server address, an alternative to the Bitcoin RPC config.
+    #[serde(default)]
+    electrum_server: Option<String>,
     bitcoin_rpc_config: Option<BitcoinRpcConfig>,
+    /// Additional Bitcoin nodes, which are used if the main one is unavailable.
+    #[serde(default)]
+    fallback_bitcoin_rpc_configs: Vec<BitcoinRpcConfig>,
+    /// Retry policy for the failed Bitcoin relay requests.
+    #[serde(default)]
+    bitcoin_relay_retry: Option<RetryConfig>,
 }
 
 impl SyncConfig {
@@ -207,6 +313,19 @@ impl SyncConfig {
             .values()
             .next()
             .map(|key| key.0.network)
+            .or_else(|| self.bitcoin_extended_key.as_ref().map(|key| key.0.network))
+    }
+
+    /// Returns the pool of Bitcoin keys including the ones derived from
+    /// the extended private key.
+    fn key_pool(&self) -> anyhow::Result<HashMap<btc::PublicKey, btc::PrivateKey>> {
+        let mut key_pool = self.bitcoin_key_pool.clone();
+        if let Some(extended_key) = &self.bitcoin_extended_key {
+            for index in 0..self.bitcoin_derived_keys {
+                key_pool.extend(std::iter::once(extended_key.derive_keypair(index)?));
+            }
+        }
+        Ok(key_pool)
     }
 
     fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
@@ -239,37 +358,336 @@ struct BitcoinRpcConfig {
     user: Option<String>,
     /// Bitcoin RPC password.
     password: Option<String>,
+    /// Path to the Bitcoin RPC cookie file, which is used instead of the username
+    /// and password.
+    #[serde(default)]
+    cookie_file: Option<PathBuf>,
 }
 
 impl TryFrom<BitcoinRpcConfig> for BitcoinRpcClient {
     type Error = bitcoincore_rpc::Error;
 
     fn try_from(value: BitcoinRpcConfig) -> Result<Self, Self::Error> {
-        let auth = BitcoinRpcAuth::UserPass(
-            value.user.unwrap_or_default(),
-            value.password.unwrap_or_default(),
-        );
+        let auth = match value.cookie_file {
+            Some(path) => BitcoinRpcAuth::CookieFile(path),
+            None => BitcoinRpcAuth::UserPass(
+                value.user.unwrap_or_default(),
+                value.password.unwrap_or_default(),
+            ),
+        };
         Self::new(value.host, auth)
     }
 }
 
+/// Bitcoin RPC relay which re-reads the cookie file after the authentication failures.
+///
+/// The Bitcoin node generates a new cookie on each start, so the client with
+/// the outdated credentials is replaced by a new one.
+#[derive(Debug)]
+struct BitcoinRpcRelay {
+    config: BitcoinRpcConfig,
+    client: RwLock<Arc<BitcoinRpcClient>>,
+}
+
+impl BitcoinRpcRelay {
+    fn new(config: BitcoinRpcConfig) -> Result<Self, bitcoincore_rpc::Error> {
+        let client = BitcoinRpcClient::try_from(config.clone())?;
+        Ok(Self {
+            config,
+            client: RwLock::new(Arc::new(client)),
+        })
+    }
+
+    fn client(&self) -> Arc<BitcoinRpcClient> {
+        self.client.read().unwrap().clone()
+    }
+
+    fn handle_error(&self, e: bitcoincore_rpc::Error) -> bitcoincore_rpc::Error {
+        // The Bitcoin node responds to the requests with the wrong credentials with
+        // the `401 Unauthorized` status and an empty body, which the RPC client fails
+        // to parse. The other errors are not related to the credentials.
+        let is_auth_error = matches!(e, bitcoincore_rpc::Error::JsonRpc(JsonRpcError::Json(_)));
+        if self.config.cookie_file.is_some() && is_auth_error {
+            match BitcoinRpcClient::try_from(self.config.clone()) {
+                Ok(client) => *self.client.write().unwrap() = Arc::new(client),
+                Err(e) => log::warn!("Unable to re-read the Bitcoin RPC cookie file: {}", e),
+            }
+        }
+        e
+    }
+}
+
+#[async_trait]
+impl BitcoinRelay for BitcoinRpcRelay {
+    type Error = bitcoincore_rpc::Error;
+
+    async fn send_transaction(
+        &self,
+        transaction: &btc::Transaction,
+    ) -> Result<btc::Sha256d, Self::Error> {
+        self.client()
+            .send_transaction(transaction)
+            .await
+            .map_err(|e| self.handle_error(e))
+    }
+
+    async fn transaction_status(&self, id: btc::Sha256d) -> Result<TransactionStatus, Self::Error> {
+        self.client()
+            .transaction_status(id)
+            .await
+            .map_err(|e| self.handle_error(e))
+    }
+
+    async fn health_check(&self) -> Result<Option<BitcoinNodeInfo>, Self::Error> {
+        self.client()
+            .health_check()
+            .await
+            .map_err(|e| self.handle_error(e))
+    }
+}
+
+/// Retry policy for the failed Bitcoin relay requests.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct RetryConfig {
+    /// Maximum number of attempts for a single request.
+    max_attempts: u32,
+    /// Delay before the second attempt in milliseconds.
+    initial_delay_ms: u64,
+    /// Maximum delay between attempts in milliseconds.
+    max_delay_ms: u64,
+    /// Fraction of the delay which can be randomly subtracted from it.
+    jitter: f64,
+}
+
+impl RetryConfig {
+    fn apply<R>(&self, relay: RetryRelay<R>) -> anyhow::Result<RetryRelay<R>> {
+        ensure!(self.max_attempts > 0, "`max_attempts` should be positive");
+        ensure!(
+            (0.0..=1.0).contains(&self.jitter),
+            "`jitter` should be in the [0, 1] range"
+        );
+        Ok(relay
+            .with_max_attempts(self.max_attempts)
+            .with_backoff(
+                Duration::from_millis(self.initial_delay_ms),
+                Duration::from_millis(self.max_delay_ms),
+            )
+            .with_jitter(self.jitter))
+    }
+}
+
+/// Bitcoin relay chosen in the sync utility config.
+#[derive(Debug)]
+enum Relay {
+    /// Bitcoin nodes RPC.
+    Rpc(FailoverRelay<BitcoinRpcRelay>),
+    /// Electrum server.
+    Electrum(ElectrumRelay),
+}
+
+impl Relay {
+    fn from_config(sync_config: &SyncConfig) -> anyhow::Result<Option<RetryRelay<Self>>> {
+        ensure!(
+            sync_config.fallback_bitcoin_rpc_configs.is_empty()
+                || sync_config.bitcoin_rpc_config.is_some(),
+            "Fallback Bitcoin RPC configs require the main `bitcoin_rpc_config`"
+        );
+        let relay = match (
+            &sync_config.bitcoin_rpc_config,
+            &sync_config.electrum_server,
+        ) {
+            (Some(_), Some(_)) => {
+                bail!("Only one of `bitcoin_rpc_config` and `electrum_server` should be specified")
+            }
+            (Some(rpc_config), None) => {
+                let clients = std::iter::once(rpc_config)
+                    .chain(&sync_config.fallback_bitcoin_rpc_configs)
+                    .cloned()
+                    .map(BitcoinRpcRelay::new)
+                    .collect::<Result<Vec<_>, _>>()?;
+                Some(Relay::Rpc(FailoverRelay::new(clients)))
+            }
+            (None, Some(address)) => Some(Relay::Electrum(ElectrumRelay::new(address.clone()))),
+            (None, None) => None,
+        };
+        relay
+            .map(|relay| {
+                let relay = RetryRelay::new(relay);
+                match &sync_config.bitcoin_relay_retry {
+                    Some(retry_config) => retry_config.apply(relay),
+                    None => Ok(relay),
+                }
+            })
+            .transpose()
+    }
+}
+
+/// Error of the Bitcoin relay chosen in the sync utility config.
+#[derive(Debug, thiserror::Error)]
+enum AnyRelayError {
+    /// Bitcoin nodes RPC error.
+    #[error(transparent)]
+    Rpc(#[from] bitcoincore_rpc::Error),
+    /// Electrum server error.
+    #[error(transparent)]
+    Electrum(#[from] ElectrumError),
+}
+
+impl RelayError for AnyRelayError {
+    fn is_transient(&self) -> bool {
+        match self {
+            AnyRelayError::Rpc(e) => e.is_transient(),
+            AnyRelayError::Electrum(e) => e.is_transient(),
+        }
+    }
+
+    fn is_transport(&self) -> bool {
+        match self {
+            AnyRelayError::Rpc(e) => e.is_transport(),
+            AnyRelayError::Electrum(e) => e.is_transport(),
+        }
+    }
+}
+
+#[async_trait]
+impl BitcoinRelay for Relay {
+    type Error = AnyRelayError;
+
+    async fn send_transaction(
+        &self,
+        transaction: &btc::Transaction,
+    ) -> Result<btc::Sha256d, Self::Error> {
+        match self {
+            Relay::Rpc(relay) => relay
+                .send_transaction(transaction)
+                .await
+                .map_err(From::from),
+            Relay::Electrum(relay) => relay
+                .send_transaction(transaction)
+                .await
+                .map_err(From::from),
+        }
+    }
+
+    async fn transaction_status(&self, id: btc::Sha256d) -> Result<TransactionStatus, Self::Error> {
+        match self {
+            Relay::Rpc(relay) => relay.transaction_status(id).await.map_err(From::from),
+            Relay::Electrum(relay) => relay.transaction_status(id).await.map_err(From::from),
+        }
+    }
+
+    async fn health_check(&self) -> Result<Option<BitcoinNodeInfo>, Self::Error> {
+        match self {
+            Relay::Rpc(relay) => relay.health_check().await.map_err(From::from),
+            Relay::Electrum(relay) => relay.health_check().await.map_err(From::from),
+        }
+    }
+}
+
+/// Bitcoin relay that writes anchoring transactions to the local journal file instead
+/// of broadcasting them to the Bitcoin network.
+///
+/// Each line of the journal contains a hex-encoded transaction. Transactions from the
+/// journal are considered to be in the memory pool, statuses of the other transactions
+/// are requested from the underlying relay.
+#[derive(Debug)]
+struct DryRunRelay<R> {
+    inner: R,
+    journal: PathBuf,
+    written: Mutex<HashSet<btc::Sha256d>>,
+}
+
+impl<R> DryRunRelay<R> {
+    fn new(inner: R, journal: PathBuf) -> anyhow::Result<Self> {
+        // Restore identifiers of the transactions written during the previous runs.
+        let written = if journal.exists() {
+            fs::read_to_string(&journal)?
+                .lines()
+                .map(|line| line.parse::<btc::Transaction>().map(|tx| tx.id()))
+                .collect::<Result<_, _>>()?
+        } else {
+            HashSet::new()
+        };
+
+        Ok(Self {
+            inner,
+            journal,
+            written: Mutex::new(written),
+        })
+    }
+}
+
+#[async_trait]
+impl<R> BitcoinRelay for DryRunRelay<R>
+where
+    R: BitcoinRelay + Send + Sync,
+    R::Error: Into<anyhow::Error>,
+{
+    type Error = anyhow::Error;
+
+    async fn send_transaction(
+        &self,
+        transaction: &btc::Transaction,
+    ) -> Result<btc::Sha256d, Self::Error> {
+        let id = transaction.id();
+        let mut written = self.written.lock().unwrap();
+        if written.insert(id) {
+            let mut journal = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.journal)?;
+            writeln!(journal, "{}", transaction)?;
+            log::info!("Wrote transaction {} to the dry run journal", id);
+        }
+        Ok(id)
+    }
+
+    async fn transaction_status(&self, id: btc::Sha256d) -> Result<TransactionStatus, Self::Error> {
+        let is_written = self.written.lock().unwrap().contains(&id);
+        if is_written {
+            return Ok(TransactionStatus::Mempool);
+        }
+        self.inner.transaction_status(id).await.map_err(Into::into)
+    }
+
+    async fn health_check(&self) -> Result<Option<BitcoinNodeInfo>, Self::Error> {
+        self.inner.health_check().await.map_err(Into::into)
+    }
+}
+
 impl GenerateConfigCommand {
     fn run(self) -> anyhow::Result<()> {
-        let bitcoin_keypair = btc::gen_keypair(self.bitcoin_network);
+        let (bitcoin_keypair, bitcoin_extended_key) = if self.extended_key {
+            let extended_key = btc::gen_extended_key(self.bitcoin_network);
+            (extended_key.derive_keypair(0)?, Some(extended_key))
+        } else {
+            (btc::gen_keypair(self.bitcoin_network), None)
+        };
 
         let bitcoin_rpc_config = self.bitcoin_rpc_config();
         let sync_config = SyncConfig {
             exonum_private_api: self.exonum_private_api,
-            bitcoin_key_pool: std::iter::once(bitcoin_keypair.clone()).collect(),
+            bitcoin_key_pool: if bitcoin_extended_key.is_some() {
+                HashMap::new()
+            } else {
+                std::iter::once(bitcoin_keypair.clone()).collect()
+            },
+            bitcoin_derived_keys: if bitcoin_extended_key.is_some() { 1 } else { 0 },
+            bitcoin_extended_key,
             instance_name: self.instance_name,
+            max_scan_depth: None,
+            low_funds_threshold: None,
+            electrum_server: self.electrum_server,
             bitcoin_rpc_config,
+            fallback_bitcoin_rpc_configs: Vec::new(),
+            bitcoin_relay_retry: None,
         };
 
         sync_config.save(self.output)?;
         log::info!("Generated initial configuration for the btc anchoring sync util.");
         log::trace!(
             "Available Bitcoin keys in key pool: {:?}",
-            sync_config.bitcoin_key_pool
+            sync_config.key_pool()?
         );
         // Print the received Bitcoin public key to use it in scripts.
         println!("{}", bitcoin_keypair.0);
@@ -281,6 +699,7 @@ impl GenerateConfigCommand {
             host,
             user: self.bitcoin_rpc_user.clone(),
             password: self.bitcoin_rpc_password.clone(),
+            cookie_file: self.bitcoin_rpc_cookie_file.clone(),
         })
     }
 }
@@ -288,18 +707,103 @@ impl GenerateConfigCommand {
 impl RunCommand {
     async fn run(self) -> anyhow::Result<()> {
         let sync_config = SyncConfig::load(self.config)?;
+        let bitcoin_relay = Relay::from_config(&sync_config)?;
+        let key_pool = sync_config.key_pool()?;
         let client = ApiClient::new(sync_config.exonum_private_api, sync_config.instance_name);
-        let chain_updater =
-            AnchoringChainUpdateTask::new(sync_config.bitcoin_key_pool, client.clone());
-        let bitcoin_relay = sync_config
-            .bitcoin_rpc_config
-            .map(BitcoinRpcClient::try_from)
-            .transpose()?
-            .map(|relay| SyncWithBitcoinTask::new(relay, client.clone()));
+        let mut chain_updater = AnchoringChainUpdateTask::new(key_pool, client.clone());
+        if let Some(threshold) = sync_config.low_funds_threshold {
+            chain_updater = chain_updater.with_low_funds_threshold(threshold);
+        }
+        let max_scan_depth = sync_config.max_scan_depth;
+
+        if let Some(journal) = self.dry_run {
+            // Without a relay anchoring transactions are not sent anywhere, so there is
+            // nothing to write to the journal.
+            ensure!(
+                bitcoin_relay.is_some(),
+                "The dry run mode requires a Bitcoin relay, specify `bitcoin_rpc_config` \
+                 or `electrum_server` in the sync utility config"
+            );
+            log::info!(
+                "Running in the dry run mode, anchoring transactions will be written to {}",
+                journal.display()
+            );
+            let bitcoin_relay = bitcoin_relay
+                .map(|relay| DryRunRelay::new(relay, journal))
+                .transpose()?
+                .map(|relay| Self::sync_task(relay, client, max_scan_depth));
+            Self::run_loop(chain_updater, bitcoin_relay).await
+        } else {
+            let bitcoin_relay =
+                bitcoin_relay.map(|relay| Self::sync_task(relay, client, max_scan_depth));
+            Self::run_loop(chain_updater, bitcoin_relay).await
+        }
+    }
+
+    fn sync_task<R>(
+        relay: R,
+        client: ApiClient,
+        max_scan_depth: Option<NonZeroU64>,
+    ) -> SyncWithBitcoinTask<ApiClient, R>
+    where
+        R: BitcoinRelay + 'static,
+        R::Error: Display,
+    {
+        let task = SyncWithBitcoinTask::new(relay, client);
+        if let Some(depth) = max_scan_depth {
+            task.with_max_scan_depth(depth)
+        } else {
+            task
+        }
+    }
+
+    async fn run_loop<R>(
+        chain_updater: AnchoringChainUpdateTask<ApiClient>,
+        bitcoin_relay: Option<SyncWithBitcoinTask<ApiClient, R>>,
+    ) -> anyhow::Result<()>
+    where
+        R: BitcoinRelay + 'static,
+        R::Error: Display,
+    {
+        // The shutdown signal interrupts the pending requests as well. It is safe, since
+        // the already signed inputs are skipped and the anchoring transactions are sent
+        // to the Bitcoin network again after the restart.
+        let shutdown = tokio::signal::ctrl_c();
+        tokio::pin!(shutdown);
+        // Awaits the given future unless the shutdown signal is received first.
+        macro_rules! or_shutdown {
+            ($future:expr) => {
+                tokio::select! {
+                    _ = &mut shutdown => {
+                        log::info!("Received shutdown signal, stopping the sync utility.");
+                        return Ok(());
+                    }
+                    output = $future => output,
+                }
+            };
+        }
 
         let mut latest_synced_tx_index: Option<u64> = None;
-        loop {
-            match chain_updater.process().await {
+        let mut bitcoin_node_synced = true;
+        for iteration in 0_u64.. {
+            if let Some(relay) = bitcoin_relay.as_ref() {
+                if iteration % HEALTH_CHECK_INTERVAL == 0 {
+                    bitcoin_node_synced =
+                        or_shutdown!(Self::check_bitcoin_node(relay.bitcoin_relay()))?;
+                }
+            }
+            if iteration % HEALTH_CHECK_INTERVAL == 0 {
+                let stats = chain_updater.stats();
+                log::info!(
+                    "Anchoring proposal inputs: {} signed, {} skipped as already signed, \
+                     {} failed",
+                    stats.signed_inputs,
+                    stats.skipped_inputs,
+                    stats.failed_inputs
+                );
+            }
+
+            match or_shutdown!(chain_updater.process()) {
                 Ok(_) => {}
                 // Client problems most often occurs due to network problems.
                 Err(ChainUpdateError::Client(e)) => {
@@ -312,9 +816,16 @@ impl RunCommand {
                     total_fee,
                     balance
                 ),
+                // The change is too small, the anchoring wallet should be replenished too.
+                Err(ChainUpdateError::DustOutput { total_fee, balance }) => log::warn!(
+                    "Anchoring output value would be below the dust limit, \
+                     total fee is {}, total balance is {}",
+                    total_fee,
+                    balance
+                ),
                 // For the work of anchoring you need to replenish anchoring wallet.
                 Err(ChainUpdateError::NoInitialFunds) => {
-                    let address = match chain_updater.anchoring_config().await {
+                    let address = match or_shutdown!(chain_updater.anchoring_config()) {
                         Ok(config) => config.anchoring_address(),
                         Err(e) => {
                             log::error!("An error in the anchoring API client occurred. {}", e);
@@ -336,8 +847,10 @@ impl RunCommand {
                 Err(ChainUpdateError::Internal(e)) => return Err(e),
             }
 
-            if let Some(relay) = bitcoin_relay.as_ref() {
-                match relay.process(latest_synced_tx_index).await {
+            // The node with an outdated view of the Bitcoin blockchain reports wrong
+            // transaction statuses, so synchronization is postponed until it catches up.
+            if let Some(relay) = bitcoin_relay.as_ref().filter(|_| bitcoin_node_synced) {
+                match or_shutdown!(relay.process(latest_synced_tx_index)) {
                     Ok(index) => latest_synced_tx_index = index,
 
                     Err(SyncWithBitcoinError::Client(e)) => {
@@ -354,14 +867,72 @@ impl RunCommand {
                         id
                     ),
 
+                    // The Bitcoin node may still be catching up, so the search is
+                    // repeated on the next iteration.
+                    Err(SyncWithBitcoinError::ScanDepthExceeded(depth)) => log::error!(
+                        "Unable to find the first uncommitted anchoring transaction among \
+                         the {} latest ones. Increase the `max_scan_depth` value or \
+                         check the Bitcoin node state.",
+                        depth
+                    ),
+
+                    Err(SyncWithBitcoinError::PolicyViolation(id, e)) => bail!(
+                        "Anchoring transaction with id {} cannot be broadcast to the Bitcoin \
+                         network. {}",
+                        id,
+                        e
+                    ),
+
                     // Stop execution if an internal error occurred.
                     Err(SyncWithBitcoinError::Internal(e)) => return Err(e),
                 }
             }
 
             // Don't perform this actions too frequent to avoid DOS attack.
-            delay_for(Duration::from_secs(5)).await
+            or_shutdown!(delay_for(Duration::from_secs(5)));
+        }
+        Ok(())
+    }
+
+    /// Checks that the Bitcoin nodes are compatible with the sync utility and returns
+    /// `false` if the node handling the requests is in the initial block download state,
+    /// so its view of the Bitcoin blockchain is outdated. The version reported by
+    /// the failover relay is the lowest one among all the reachable nodes.
+    async fn check_bitcoin_node<R>(relay: &R) -> anyhow::Result<bool>
+    where
+        R: BitcoinRelay,
+        R::Error: Display,
+    {
+        let info = match relay.health_check().await {
+            Ok(Some(info)) => info,
+            Ok(None) => return Ok(true),
+            Err(e) => {
+                log::error!("An error in the Bitcoin relay occurred. {}", e);
+                return Ok(true);
+            }
+        };
+
+        if let Some(version) = info.version {
+            ensure!(
+                version >= MIN_BITCOIND_VERSION,
+                "Bitcoin node version {} is not supported, the minimal supported version is {}",
+                version,
+                MIN_BITCOIND_VERSION
+            );
+        }
+        if info.initial_block_download {
+            log::warn!(
+                "Bitcoin node is in the initial block download state, its best known block \
+                 height {} is outdated. Synchronization with Bitcoin is suspended.",
+                info.blocks
+            );
+            return Ok(false);
         }
+        log::trace!(
+            "Bitcoin node is healthy, best known block height is {}",
+            info.blocks
+        );
+        Ok(true)
     }
 }
 
@@ -375,12 +946,19 @@ impl GenerateKeypairCommand {
                  Perhaps pool of keys in config is empty."
             )
         })?;
-        let bitcoin_keypair = btc::gen_keypair(network);
-        let bitcoin_pub_key = bitcoin_keypair.0;
-
-        sync_config
-            .bitcoin_key_pool
-            .extend(std::iter::once(bitcoin_keypair));
+        let bitcoin_pub_key = if let Some(extended_key) = &sync_config.bitcoin_extended_key {
+            // The key is restored from the extended key, so only the counter is stored.
+            let bitcoin_keypair = extended_key.derive_keypair(sync_config.bitcoin_derived_keys)?;
+            sync_config.bitcoin_derived_keys += 1;
+            bitcoin_keypair.0
+        } else {
+            let bitcoin_keypair = btc::gen_keypair(network);
+            let bitcoin_pub_key = bitcoin_keypair.0;
+            sync_config
+                .bitcoin_key_pool
+                .extend(std::iter::once(bitcoin_keypair));
+            bitcoin_pub_key
+        };
         sync_config.save(self.config)?;
         // Print the received Bitcoin public key to use it in scripts.
         println!("{}", bitcoin_pub_key);
@@ -388,12 +966,31 @@ impl GenerateKeypairCommand {
     }
 }
 
+impl TransactionsSummaryCommand {
+    async fn run(self) -> anyhow::Result<()> {
+        let sync_config = SyncConfig::load(&self.config)?;
+        let bitcoin_relay = Relay::from_config(&sync_config)?.ok_or_else(|| {
+            anyhow!(
+                "Bitcoin confirmations require a Bitcoin relay, specify `bitcoin_rpc_config` \
+                 or `electrum_server` in the sync utility config"
+            )
+        })?;
+        let client = ApiClient::new(self.exonum_public_api, sync_config.instance_name);
+
+        let mut summaries = client.transactions_summary(self.from, self.count).await?;
+        sync::fill_confirmations(&bitcoin_relay, &mut summaries).await?;
+        println!("{}", serde_json::to_string_pretty(&summaries)?);
+        Ok(())
+    }
+}
+
 impl Commands {
     async fn run(self) -> anyhow::Result<()> {
         match self {
             Commands::GenerateConfig(cmd) => cmd.run(),
             Commands::GenerateKeypair(cmd) => cmd.run(),
             Commands::Run(cmd) => cmd.run().await,
+            Commands::TransactionsSummary(cmd) => cmd.run().await,
         }
     }
 }
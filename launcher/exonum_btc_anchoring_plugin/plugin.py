@@ -10,7 +10,7 @@ from exonum_launcher.instances.instance_spec_loader import InstanceSpecLoader, I
 
 RUST_RUNTIME_ID = 0
 ANCHORING_ARTIFACT_NAME = "exonum-btc-anchoring"
-ANCHORING_ARTIFACT_VERSION = "1.0.0"
+ANCHORING_ARTIFACT_VERSION = "1.1.0"
 
 
 def import_anchoring_module(name: str):
@@ -46,6 +46,7 @@ class AnchoringInstanceSpecLoader(InstanceSpecLoader):
                 instance.config["network"])
             config.anchoring_interval = instance.config["anchoring_interval"]
             config.transaction_fee = instance.config["transaction_fee"]
+            config.replaceable = instance.config.get("replaceable", False)
 
             anchoring_keys = []
             for keypair in instance.config["anchoring_keys"]:
@@ -37,9 +37,9 @@ use std::cmp::{
 };
 
 use crate::{
-    blockchain::{AddFunds, BtcAnchoringInterface, Schema, SignInput},
+    blockchain::{data_layout::TxInputId, AddFunds, BtcAnchoringInterface, Schema, SignInput},
     btc,
-    config::Config,
+    config::{derive_redeem_script, Config},
 };
 
 /// A proof of existence for an anchoring transaction at the given height.
@@ -51,6 +51,15 @@ pub struct TransactionProof {
     pub transaction_proof: ListProof<btc::Transaction>,
 }
 
+/// A proof of existence for a range of anchoring transactions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionsRangeProof {
+    /// Proof of authenticity for a transactions index within the database.
+    pub index_proof: IndexProof,
+    /// Proof for the transactions with indices from the requested range in this table.
+    pub transactions_proof: ListProof<btc::Transaction>,
+}
+
 /// State of the next anchoring transaction proposal.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AnchoringProposalState {
@@ -72,6 +81,14 @@ pub enum AnchoringProposalState {
         /// Available balance.
         balance: u64,
     },
+    /// Anchoring output value after paying the fee would be below the dust limit.
+    /// Please fill up an anchoring wallet.
+    DustOutput {
+        /// Total transaction fee.
+        total_fee: u64,
+        /// Available balance.
+        balance: u64,
+    },
     /// Initial funding transaction is absent.
     NoInitialFunds,
 }
@@ -89,12 +106,116 @@ impl AnchoringProposalState {
             Some(Err(btc::BuilderError::InsufficientFunds { total_fee, balance })) => {
                 Ok(AnchoringProposalState::InsufficientFunds { total_fee, balance })
             }
+            Some(Err(btc::BuilderError::DustOutput { total_fee, balance })) => {
+                Ok(AnchoringProposalState::DustOutput { total_fee, balance })
+            }
             Some(Err(btc::BuilderError::NoInputs)) => Ok(AnchoringProposalState::NoInitialFunds),
             Some(Err(e)) => Err(api::Error::internal(e)),
         }
     }
 }
 
+/// Anchoring address derived from the given Bitcoin public keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DerivedAddress {
+    /// Hex-encoded redeem script.
+    pub redeem_script: String,
+    /// Number of signatures required to spend funds.
+    pub quorum: usize,
+    /// P2WSH address for the redeem script in the actual Bitcoin network.
+    pub address: btc::Address,
+}
+
+/// Balance of the anchoring wallet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnchoringBalance {
+    /// Total amount of satoshis available for anchoring, including the unspent
+    /// funding transaction.
+    pub balance: u64,
+    /// Estimated fee of a single anchoring transaction in satoshis, based on the size
+    /// of the latest anchoring transaction and the actual fee per byte.
+    pub fee_per_anchor: Option<u64>,
+    /// Estimated number of anchoring transactions that can be paid with the balance.
+    pub anchors_remaining: Option<u64>,
+}
+
+/// Information about the transaction from the anchoring chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnchoringTransactionInfo {
+    /// Index of the transaction in the anchoring chain.
+    pub index: u64,
+    /// Anchoring payload of the transaction.
+    pub payload: btc::Payload,
+}
+
+/// Summary of the anchoring process state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnchoringStatus {
+    /// Whether the anchoring is in transition to the following configuration.
+    pub is_transition: bool,
+    /// Height of the latest anchored block, if any.
+    pub latest_anchored_height: Option<Height>,
+    /// Height of the next block to be anchored.
+    pub next_anchoring_height: Height,
+    /// Number of blocks committed after the next block to be anchored.
+    /// A growing value means that the anchoring falls behind the blockchain.
+    pub lag: u64,
+    /// Whether the anchoring proposal is waiting for the signatures.
+    pub proposal_pending: bool,
+    /// Total amount of satoshis available for anchoring.
+    pub balance: u64,
+}
+
+/// Statistics of the anchoring wallet spending.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnchoringSpendingStats {
+    /// Number of transactions in the anchoring chain.
+    pub transactions_count: u64,
+    /// Total amount of satoshis spent on the fees of the anchoring transactions
+    /// with the known fee.
+    pub total_fee: u64,
+    /// Average fee of the anchoring transaction in satoshis, if any fee is known.
+    pub average_fee: Option<u64>,
+    /// Number of anchoring transactions whose fee cannot be calculated, because
+    /// some of the spent transactions are unknown to the service.
+    pub unknown_fee_count: u64,
+    /// Total amount of satoshis available for anchoring.
+    pub balance: u64,
+}
+
+/// Summary of the transaction from the anchoring chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnchoringTransactionSummary {
+    /// Index of the transaction in the anchoring chain.
+    pub index: u64,
+    /// Bitcoin transaction identifier.
+    pub txid: btc::Sha256d,
+    /// Anchoring payload of the transaction.
+    pub payload: btc::Payload,
+    /// Fee paid by the transaction in satoshis. It is absent if some of
+    /// the spent transactions are unknown to the service.
+    pub fee: Option<u64>,
+    /// Number of confirmations of the transaction in the Bitcoin blockchain.
+    /// The service does not track the Bitcoin blockchain, so this field is filled
+    /// by [`sync::fill_confirmations`] and is absent in the service API responses.
+    ///
+    /// [`sync::fill_confirmations`]: ../sync/fn.fill_confirmations.html
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u32>,
+}
+
+/// Signature hashes of the anchoring transaction proposal inputs.
+///
+/// These hashes can be signed by an external custodian of the anchoring node Bitcoin key,
+/// the resulting signatures should be sent back via the `sign-input` endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposalSighashes {
+    /// Identifier of the anchoring transaction proposal.
+    pub txid: btc::Sha256d,
+    /// Signature hashes of the proposal inputs in order of their indices.
+    pub sighashes: Vec<btc::Sha256d>,
+}
+
 /// Total length of anchoring transaction chain.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AnchoringChainLength {
@@ -163,6 +284,119 @@ pub trait PublicApi {
     ///
     /// [`config`]: ../config/struct.Config.html
     async fn config(&self) -> Result<Config, Self::Error>;
+    /// Returns anchoring transactions with indices in the `[from, to)` range along
+    /// with the proof of their authenticity. Indices beyond the chain length are
+    /// ignored, the range length must not exceed [`MAX_TRANSACTIONS_RANGE`].
+    ///
+    /// | Property    | Value |
+    /// |-------------|-------|
+    /// | Path        | `/api/services/{btc_anchoring}/transactions-range` |
+    /// | Method      | GET   |
+    /// | Query type  | [`TransactionsRangeQuery`] |
+    /// | Return type | [`TransactionsRangeProof`] |
+    ///
+    /// [`MAX_TRANSACTIONS_RANGE`]: constant.MAX_TRANSACTIONS_RANGE.html
+    /// [`TransactionsRangeQuery`]: struct.TransactionsRangeQuery.html
+    /// [`TransactionsRangeProof`]: struct.TransactionsRangeProof.html
+    async fn transactions_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<TransactionsRangeProof, Self::Error>;
+    /// Returns summaries of `count` anchoring transactions starting from the one with
+    /// the `from` index, including the paid fees. Indices beyond the chain length are
+    /// ignored, the count must not exceed [`MAX_TRANSACTIONS_RANGE`]. The numbers of
+    /// Bitcoin confirmations can be added by [`sync::fill_confirmations`].
+    ///
+    /// | Property    | Value |
+    /// |-------------|-------|
+    /// | Path        | `/api/services/{btc_anchoring}/transactions-summary` |
+    /// | Method      | GET   |
+    /// | Query type  | [`TransactionsPageQuery`] |
+    /// | Return type | [`Vec<AnchoringTransactionSummary>`] |
+    ///
+    /// [`MAX_TRANSACTIONS_RANGE`]: constant.MAX_TRANSACTIONS_RANGE.html
+    /// [`sync::fill_confirmations`]: ../sync/fn.fill_confirmations.html
+    /// [`TransactionsPageQuery`]: struct.TransactionsPageQuery.html
+    /// [`Vec<AnchoringTransactionSummary>`]: struct.AnchoringTransactionSummary.html
+    async fn transactions_summary(
+        &self,
+        from: u64,
+        count: u64,
+    ) -> Result<Vec<AnchoringTransactionSummary>, Self::Error>;
+    /// Returns information about the anchoring transaction with the specified
+    /// identifier, if this transaction belongs to the anchoring chain.
+    ///
+    /// | Property    | Value |
+    /// |-------------|-------|
+    /// | Path        | `/api/services/{btc_anchoring}/transaction-info` |
+    /// | Method      | GET   |
+    /// | Query type  | [`TxIdQuery`] |
+    /// | Return type | [`Option<AnchoringTransactionInfo>`] |
+    ///
+    /// [`TxIdQuery`]: struct.TxIdQuery.html
+    /// [`Option<AnchoringTransactionInfo>`]: struct.AnchoringTransactionInfo.html
+    async fn transaction_info(
+        &self,
+        txid: btc::Sha256d,
+    ) -> Result<Option<AnchoringTransactionInfo>, Self::Error>;
+    /// Returns the balance of the anchoring wallet and the estimated number
+    /// of the remaining anchors.
+    ///
+    /// The estimation is absent until the anchoring chain is established.
+    ///
+    /// | Property    | Value |
+    /// |-------------|-------|
+    /// | Path        | `/api/services/{btc_anchoring}/balance` |
+    /// | Method      | GET   |
+    /// | Query type  | - |
+    /// | Return type | [`AnchoringBalance`] |
+    ///
+    /// [`AnchoringBalance`]: struct.AnchoringBalance.html
+    async fn balance(&self) -> Result<AnchoringBalance, Self::Error>;
+    /// Returns the summary of the anchoring process state.
+    ///
+    /// | Property    | Value |
+    /// |-------------|-------|
+    /// | Path        | `/api/services/{btc_anchoring}/status` |
+    /// | Method      | GET   |
+    /// | Query type  | - |
+    /// | Return type | [`AnchoringStatus`] |
+    ///
+    /// [`AnchoringStatus`]: struct.AnchoringStatus.html
+    async fn status(&self) -> Result<AnchoringStatus, Self::Error>;
+    /// Returns the statistics of the fees paid by the anchoring transactions and
+    /// the remaining balance of the anchoring wallet.
+    ///
+    /// The statistics are accumulated by the service as the anchoring chain grows.
+    ///
+    /// | Property    | Value |
+    /// |-------------|-------|
+    /// | Path        | `/api/services/{btc_anchoring}/spending-stats` |
+    /// | Method      | GET   |
+    /// | Query type  | - |
+    /// | Return type | [`AnchoringSpendingStats`] |
+    ///
+    /// [`AnchoringSpendingStats`]: struct.AnchoringSpendingStats.html
+    async fn spending_stats(&self) -> Result<AnchoringSpendingStats, Self::Error>;
+    /// Derives the anchoring address for the given Bitcoin public keys and quorum
+    /// in the actual Bitcoin network. This method does not change the service state,
+    /// it allows checking a proposed configuration before applying it. The number
+    /// of keys must not exceed 20, which is the limit of the Bitcoin multisig script.
+    ///
+    /// | Property    | Value |
+    /// |-------------|-------|
+    /// | Path        | `/api/services/{btc_anchoring}/address/derive` |
+    /// | Method      | GET   |
+    /// | Query type  | [`DeriveAddressQuery`] |
+    /// | Return type | [`DerivedAddress`] |
+    ///
+    /// [`DeriveAddressQuery`]: struct.DeriveAddressQuery.html
+    /// [`DerivedAddress`]: struct.DerivedAddress.html
+    async fn derive_address(
+        &self,
+        query: DeriveAddressQuery,
+    ) -> Result<DerivedAddress, Self::Error>;
 }
 
 /// Private API client for the Exonum Bitcoin anchoring service.
@@ -210,6 +444,35 @@ pub trait PrivateApi {
     ///
     /// [`AnchoringProposalState`]: enum.AnchoringProposalState.html
     async fn anchoring_proposal(&self) -> Result<AnchoringProposalState, Self::Error>;
+    /// Returns signature hashes for the inputs of the anchoring transaction proposal,
+    /// if the proposal is available.
+    ///
+    /// | Property    | Value |
+    /// |-------------|-------|
+    /// | Path        | `/api/services/{btc_anchoring}/anchoring-proposal/sighashes` |
+    /// | Method      | GET   |
+    /// | Query type  | - |
+    /// | Return type | [`Option<ProposalSighashes>`] |
+    ///
+    /// [`Option<ProposalSighashes>`]: struct.ProposalSighashes.html
+    async fn anchoring_proposal_sighashes(&self) -> Result<Option<ProposalSighashes>, Self::Error>;
+    /// Returns indices of the anchoring transaction proposal inputs that have already been
+    /// signed by the anchoring node with the given Bitcoin key. If the given transaction
+    /// is not the actual proposal, an empty list is returned.
+    ///
+    /// | Property    | Value |
+    /// |-------------|-------|
+    /// | Path        | `/api/services/{btc_anchoring}/anchoring-proposal/signed-inputs` |
+    /// | Method      | GET   |
+    /// | Query type  | [`SignedInputsQuery`] |
+    /// | Return type | `Vec<u32>` |
+    ///
+    /// [`SignedInputsQuery`]: struct.SignedInputsQuery.html
+    async fn signed_inputs(
+        &self,
+        txid: btc::Sha256d,
+        bitcoin_key: btc::PublicKey,
+    ) -> Result<Vec<u32>, Self::Error>;
     /// Returns an actual anchoring configuration.
     ///
     /// | Property    | Value |
@@ -311,18 +574,20 @@ impl ApiImpl {
         Ok(())
     }
 
-    fn transaction_proof(&self, tx_index: u64) -> TransactionProof {
-        let index_proof = self
-            .0
+    fn transactions_index_proof(&self) -> IndexProof {
+        self.0
             .data()
             .proof_for_service_index("transactions_chain")
-            .unwrap();
+            .unwrap()
+    }
+
+    fn transaction_proof(&self, tx_index: u64) -> TransactionProof {
         let transaction_proof = Schema::new(self.0.service_data())
             .transactions_chain
             .get_proof(tx_index);
 
         TransactionProof {
-            index_proof,
+            index_proof: self.transactions_index_proof(),
             transaction_proof,
         }
     }
@@ -391,6 +656,180 @@ impl ApiImpl {
     async fn config(self) -> api::Result<Config> {
         self.actual_config().map_err(api::Error::internal)
     }
+
+    async fn transactions_range(self, from: u64, to: u64) -> api::Result<TransactionsRangeProof> {
+        check_transactions_range(from, to)?;
+
+        let transactions_proof = Schema::new(self.0.service_data())
+            .transactions_chain
+            .get_range_proof(from..to);
+        Ok(TransactionsRangeProof {
+            index_proof: self.transactions_index_proof(),
+            transactions_proof,
+        })
+    }
+
+    async fn transactions_summary(
+        self,
+        from: u64,
+        count: u64,
+    ) -> api::Result<Vec<AnchoringTransactionSummary>> {
+        if count > MAX_TRANSACTIONS_RANGE {
+            return Err(api::Error::bad_request()
+                .title("Invalid transactions count")
+                .detail(format!(
+                    "Count {} is greater than {} transactions",
+                    count, MAX_TRANSACTIONS_RANGE
+                )));
+        }
+
+        let schema = Schema::new(self.0.service_data());
+        let tx_chain = &schema.transactions_chain;
+        let to = cmp::min(from.saturating_add(count), tx_chain.len());
+        let mut prev_tx = from.checked_sub(1).and_then(|index| tx_chain.get(index));
+
+        let mut summaries = Vec::new();
+        for index in from..to {
+            let transaction = tx_chain.get(index).unwrap();
+            summaries.push(AnchoringTransactionSummary {
+                index,
+                txid: transaction.id(),
+                // Transactions in the anchoring chain always have a payload.
+                payload: transaction.anchoring_payload().unwrap(),
+                fee: schema.transaction_fee(prev_tx.as_ref(), &transaction),
+                confirmations: None,
+            });
+            prev_tx = Some(transaction);
+        }
+        Ok(summaries)
+    }
+
+    async fn transaction_info(
+        self,
+        txid: btc::Sha256d,
+    ) -> api::Result<Option<AnchoringTransactionInfo>> {
+        let schema = Schema::new(self.0.service_data());
+        Ok(schema.transaction_index(&txid).map(|index| {
+            let transaction = schema.transactions_chain.get(index).unwrap();
+            AnchoringTransactionInfo {
+                index,
+                // Transactions in the anchoring chain always have a payload.
+                payload: transaction.anchoring_payload().unwrap(),
+            }
+        }))
+    }
+
+    async fn derive_address(self, query: DeriveAddressQuery) -> api::Result<DerivedAddress> {
+        if query.bitcoin_keys.len() > Config::MAX_NODES_COUNT {
+            return Err(api::Error::bad_request()
+                .title("Unable to derive anchoring address")
+                .detail(format!(
+                    "Too many Bitcoin keys: {}, the maximum is {}",
+                    query.bitcoin_keys.len(),
+                    Config::MAX_NODES_COUNT
+                )));
+        }
+
+        let redeem_script =
+            derive_redeem_script(query.bitcoin_keys, query.quorum).map_err(|e| {
+                api::Error::bad_request()
+                    .title("Unable to derive anchoring address")
+                    .detail(e.to_string())
+            })?;
+        let network = Schema::new(self.0.service_data()).actual_config().network;
+
+        Ok(DerivedAddress {
+            redeem_script: hex::encode(redeem_script.as_ref().as_bytes()),
+            quorum: redeem_script.content().quorum,
+            address: p2wsh::address(&redeem_script, network).into(),
+        })
+    }
+
+    async fn balance(self) -> api::Result<AnchoringBalance> {
+        let schema = Schema::new(self.0.service_data());
+        let config = schema.actual_config();
+        let latest_transaction = schema.transactions_chain.last();
+        let balance = self.wallet_balance();
+
+        let fee_per_anchor =
+            latest_transaction.map(|tx| tx.unsigned_size() * config.transaction_fee);
+        let anchors_remaining = fee_per_anchor
+            .filter(|&fee| fee > 0)
+            .map(|fee| balance / fee);
+
+        Ok(AnchoringBalance {
+            balance,
+            fee_per_anchor,
+            anchors_remaining,
+        })
+    }
+
+    async fn status(self) -> api::Result<AnchoringStatus> {
+        let core_schema = self.0.data().for_core();
+        let schema = Schema::new(self.0.service_data());
+        let actual_state = schema.actual_state();
+
+        let latest_anchored_height = schema.latest_anchored_height();
+        let next_anchoring_height = actual_state.following_anchoring_height(latest_anchored_height);
+        let lag = core_schema
+            .height()
+            .0
+            .saturating_sub(next_anchoring_height.0);
+        let proposal_pending = matches!(
+            schema.proposed_anchoring_transaction(core_schema, &actual_state),
+            Some(Ok(_))
+        );
+
+        Ok(AnchoringStatus {
+            is_transition: actual_state.is_transition(),
+            latest_anchored_height,
+            next_anchoring_height,
+            lag,
+            proposal_pending,
+            balance: self.wallet_balance(),
+        })
+    }
+
+    async fn spending_stats(self) -> api::Result<AnchoringSpendingStats> {
+        let schema = Schema::new(self.0.service_data());
+        let transactions_count = schema.transactions_chain.len();
+        let total_fee = schema.total_fee.get().unwrap_or_default();
+        let known_fee_count = schema.known_fee_count.get().unwrap_or_default();
+
+        Ok(AnchoringSpendingStats {
+            transactions_count,
+            total_fee,
+            average_fee: if known_fee_count > 0 {
+                Some(total_fee / known_fee_count)
+            } else {
+                None
+            },
+            unknown_fee_count: transactions_count.saturating_sub(known_fee_count),
+            balance: self.wallet_balance(),
+        })
+    }
+
+    /// Returns the total amount of satoshis available for anchoring, including
+    /// the unspent funding transaction.
+    fn wallet_balance(&self) -> u64 {
+        let schema = Schema::new(self.0.service_data());
+        let config = schema.actual_config();
+
+        let chain_balance = schema
+            .transactions_chain
+            .last()
+            .as_ref()
+            .and_then(btc::Transaction::unspent_value)
+            .unwrap_or_default();
+        let funding_balance = schema
+            .unspent_funding_transaction()
+            .and_then(|tx| {
+                tx.find_out(&config.anchoring_out_script())
+                    .map(|(_, out)| out.value)
+            })
+            .unwrap_or_default();
+        chain_balance + funding_balance
+    }
 }
 
 /// Private API implementation
@@ -431,6 +870,70 @@ impl ApiImpl {
         )
     }
 
+    async fn anchoring_proposal_sighashes(self) -> api::Result<Option<ProposalSighashes>> {
+        let core_schema = self.0.data().for_core();
+        let anchoring_schema = Schema::new(self.0.service_data());
+
+        let (proposal, inputs) = match AnchoringProposalState::try_from_proposal(
+            anchoring_schema.actual_proposed_anchoring_transaction(core_schema),
+        )? {
+            AnchoringProposalState::Available {
+                transaction,
+                inputs,
+            } => (transaction, inputs),
+            _ => return Ok(None),
+        };
+
+        let mut signer = p2wsh::InputSigner::new(anchoring_schema.actual_config().redeem_script());
+        let sighashes = inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let sighash =
+                    signer.signature_hash(TxInRef::new(proposal.as_ref(), index), input.as_ref());
+                btc::Sha256d(sighash.into())
+            })
+            .collect();
+
+        Ok(Some(ProposalSighashes {
+            txid: proposal.id(),
+            sighashes,
+        }))
+    }
+
+    async fn signed_inputs(self, query: SignedInputsQuery) -> api::Result<Vec<u32>> {
+        let core_schema = self.0.data().for_core();
+        let anchoring_schema = Schema::new(self.0.service_data());
+
+        let node_id = anchoring_schema
+            .actual_config()
+            .anchoring_keys
+            .iter()
+            .position(|keys| keys.bitcoin_key == query.bitcoin_key)
+            .ok_or_else(|| {
+                api::Error::bad_request()
+                    .title("Unknown anchoring key")
+                    .detail(format!(
+                        "Bitcoin key {} is not in the actual anchoring configuration",
+                        query.bitcoin_key
+                    ))
+            })? as u16;
+
+        let inputs_count = match anchoring_schema.actual_proposed_anchoring_transaction(core_schema)
+        {
+            Some(Ok((proposal, inputs))) if proposal.id() == query.txid => inputs.len() as u32,
+            _ => return Ok(Vec::new()),
+        };
+
+        Ok((0..inputs_count)
+            .filter(|&index| {
+                anchoring_schema
+                    .input_signatures(&TxInputId::new(query.txid, index))
+                    .contains(node_id)
+            })
+            .collect())
+    }
+
     async fn transaction_with_index(self, index: u64) -> api::Result<Option<btc::Transaction>> {
         Ok(Schema::new(self.0.service_data())
             .transactions_chain
@@ -452,6 +955,102 @@ pub struct FindTransactionQuery {
     pub height: Option<Height>,
 }
 
+/// Maximum number of anchoring transactions in the single range proof.
+pub const MAX_TRANSACTIONS_RANGE: u64 = 1_000;
+
+/// Checks that the `[from, to)` range of anchoring transactions is well-formed
+/// and does not exceed the maximum length.
+fn check_transactions_range(from: u64, to: u64) -> api::Result<()> {
+    if from > to || to - from > MAX_TRANSACTIONS_RANGE {
+        return Err(api::Error::bad_request()
+            .title("Invalid transactions range")
+            .detail(format!(
+                "Range [{}, {}) is malformed or longer than {} transactions",
+                from, to, MAX_TRANSACTIONS_RANGE
+            )));
+    }
+    Ok(())
+}
+
+/// Query parameters for the transactions range request.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TransactionsRangeQuery {
+    /// Index of the first anchoring transaction in the range.
+    pub from: u64,
+    /// Index of the anchoring transaction following the last one in the range.
+    pub to: u64,
+}
+
+/// Query parameters for the transactions summary request.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TransactionsPageQuery {
+    /// Index of the first anchoring transaction in the page.
+    pub from: u64,
+    /// Maximum number of anchoring transactions in the page.
+    pub count: u64,
+}
+
+/// Query parameters for the address derivation request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeriveAddressQuery {
+    /// Bitcoin public keys of the anchoring nodes. In the query string they are given
+    /// as a comma-separated list of hex-encoded keys.
+    #[serde(with = "comma_separated")]
+    pub bitcoin_keys: Vec<btc::PublicKey>,
+    /// Number of signatures required to spend funds. If not specified, the byzantine
+    /// quorum is used.
+    pub quorum: Option<usize>,
+}
+
+/// Serialization of lists as comma-separated strings, since the sequences cannot
+/// be passed in the URL query.
+mod comma_separated {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use std::{fmt::Display, str::FromStr};
+
+    pub fn serialize<T, S>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ToString,
+        S: Serializer,
+    {
+        let values = values.iter().map(ToString::to_string).collect::<Vec<_>>();
+        serializer.serialize_str(&values.join(","))
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        let values = String::deserialize(deserializer)?;
+        if values.is_empty() {
+            return Ok(Vec::new());
+        }
+        values
+            .split(',')
+            .map(|value| value.trim().parse().map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+/// Query parameters for the transaction info request.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TxIdQuery {
+    /// Bitcoin transaction identifier.
+    pub txid: btc::Sha256d,
+}
+
+/// Query parameters for the signed inputs request.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SignedInputsQuery {
+    /// Identifier of the anchoring transaction proposal.
+    pub txid: btc::Sha256d,
+    /// Bitcoin key of the anchoring node.
+    pub bitcoin_key: btc::PublicKey,
+}
+
 /// Query parameters for the anchoring transaction request.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct IndexQuery {
@@ -471,7 +1070,30 @@ pub(crate) fn wire(builder: &mut ServiceApiBuilder) {
         .endpoint("find-transaction", |state, query: FindTransactionQuery| {
             ApiImpl(state).find_transaction(query.height)
         })
-        .endpoint("config", |state, _query: ()| ApiImpl(state).config());
+        .endpoint("config", |state, _query: ()| ApiImpl(state).config())
+        .endpoint(
+            "transactions-range",
+            |state, query: TransactionsRangeQuery| {
+                ApiImpl(state).transactions_range(query.from, query.to)
+            },
+        )
+        .endpoint(
+            "transactions-summary",
+            |state, query: TransactionsPageQuery| {
+                ApiImpl(state).transactions_summary(query.from, query.count)
+            },
+        )
+        .endpoint("transaction-info", |state, query: TxIdQuery| {
+            ApiImpl(state).transaction_info(query.txid)
+        })
+        .endpoint("balance", |state, _query: ()| ApiImpl(state).balance())
+        .endpoint("status", |state, _query: ()| ApiImpl(state).status())
+        .endpoint("spending-stats", |state, _query: ()| {
+            ApiImpl(state).spending_stats()
+        })
+        .endpoint("address/derive", |state, query: DeriveAddressQuery| {
+            ApiImpl(state).derive_address(query)
+        });
     builder
         .private_scope()
         .endpoint_mut("sign-input", |state, query: SignInput| {
@@ -483,6 +1105,13 @@ pub(crate) fn wire(builder: &mut ServiceApiBuilder) {
         .endpoint("anchoring-proposal", |state, _query: ()| {
             ApiImpl(state).anchoring_proposal()
         })
+        .endpoint("anchoring-proposal/sighashes", |state, _query: ()| {
+            ApiImpl(state).anchoring_proposal_sighashes()
+        })
+        .endpoint(
+            "anchoring-proposal/signed-inputs",
+            |state, query: SignedInputsQuery| ApiImpl(state).signed_inputs(query),
+        )
         .endpoint("config", |state, _query: ()| ApiImpl(state).config())
         .endpoint("transaction", |state, query: IndexQuery| {
             ApiImpl(state).transaction_with_index(query.index)
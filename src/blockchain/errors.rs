@@ -36,6 +36,8 @@ pub enum Error {
     AlreadyUsedFundingTx = 5,
     /// Funding transaction is unsuitable.
     UnsuitableFundingTx = 6,
+    /// The transaction input has been already signed by this anchoring node.
+    InputAlreadySigned = 7,
 }
 
 impl Error {
@@ -40,6 +40,13 @@ pub type TransactionConfirmations = BinaryMap<btc::PublicKey, ()>;
 pub struct Schema<T: Access> {
     /// Complete chain of the anchoring transactions.
     pub transactions_chain: ProofListIndex<T::Base, Transaction>,
+    /// Indices of the anchoring transactions in the chain by their identifiers.
+    pub(crate) transaction_indices: ProofMapIndex<T::Base, Sha256d, u64>,
+    /// Total amount of satoshis spent on the fees of the anchoring transactions
+    /// with the known fee.
+    pub(crate) total_fee: Entry<T::Base, u64>,
+    /// Number of the anchoring transactions with the known fee.
+    pub(crate) known_fee_count: Entry<T::Base, u64>,
     /// Already spent funding transactions.
     pub(crate) spent_funding_transactions: ProofMapIndex<T::Base, Sha256d, Transaction>,
     /// Signatures for the given transaction input.
@@ -85,6 +92,44 @@ impl<T: Access> Schema<T> {
         self.unspent_funding_transaction.get()
     }
 
+    /// Returns a funding transaction with the given identifier if it has been already
+    /// spent by one of the anchoring transactions.
+    pub fn spent_funding_transaction(&self, txid: &Sha256d) -> Option<Transaction> {
+        self.spent_funding_transactions.get(txid)
+    }
+
+    /// Returns the index of the anchoring transaction with the given identifier
+    /// in the anchoring chain.
+    pub fn transaction_index(&self, txid: &Sha256d) -> Option<u64> {
+        self.transaction_indices.get(txid)
+    }
+
+    /// Calculates the fee paid by the anchoring transaction. The inputs spend either
+    /// the previous anchoring transaction or the spent funding transactions.
+    ///
+    /// Returns `None` if some of the spent transactions are unknown.
+    pub fn transaction_fee(&self, prev_tx: Option<&Transaction>, tx: &Transaction) -> Option<u64> {
+        let inputs_value = tx
+            .0
+            .input
+            .iter()
+            .map(|input| {
+                let txid = Sha256d::from(input.previous_output.txid);
+                let vout = input.previous_output.vout as usize;
+                match prev_tx {
+                    Some(prev_tx) if prev_tx.id() == txid => {
+                        prev_tx.0.output.get(vout).map(|out| out.value)
+                    }
+                    _ => self
+                        .spent_funding_transaction(&txid)
+                        .and_then(|funding_tx| funding_tx.0.output.get(vout).map(|out| out.value)),
+                }
+            })
+            .sum::<Option<u64>>()?;
+        let outputs_value = tx.0.output.iter().map(|out| out.value).sum::<u64>();
+        inputs_value.checked_sub(outputs_value)
+    }
+
     /// Returns an actual state of anchoring.
     pub fn actual_state(&self) -> BtcAnchoringState {
         let actual_configuration = self.actual_config();
@@ -160,6 +205,7 @@ impl<T: Access> Schema<T> {
 
         builder.payload(anchoring_height, anchoring_block_hash);
         builder.fee(config.transaction_fee);
+        builder.replaceable(config.replaceable);
 
         // Create anchoring proposal.
         Some(builder.create())
@@ -225,6 +271,15 @@ where
             self.following_config.remove();
             self.actual_config.set(config);
         }
+        let fee = self.transaction_fee(self.transactions_chain.last().as_ref(), &tx);
+        if let Some(fee) = fee {
+            self.total_fee
+                .set(self.total_fee.get().unwrap_or_default() + fee);
+            self.known_fee_count
+                .set(self.known_fee_count.get().unwrap_or_default() + 1);
+        }
+        self.transaction_indices
+            .put(&tx.id(), self.transactions_chain.len());
         self.transactions_chain.push(tx);
     }
 
@@ -58,6 +58,11 @@ impl InputSignatures {
         self.0.len()
     }
 
+    /// Returns `true` if the map contains a value for the specified key.
+    pub(crate) fn contains(&self, id: u16) -> bool {
+        self.0.contains_key(&id)
+    }
+
     /// Inserts a key-value pair into the map.
     fn insert(&mut self, id: u16, signature: btc::InputSignature) {
         self.0.insert(id, signature);
@@ -142,7 +147,10 @@ impl BtcAnchoringInterface<ExecutionContext<'_>> for BtcAnchoringService {
         };
 
         // Make sure txid is equal to the identifier of the anchoring transaction proposal.
-        if proposal.id() != arg.txid {
+        // Computing the identifier requires the transaction serialization, so we compute
+        // it only once.
+        let proposal_id = proposal.id();
+        if proposal_id != arg.txid {
             return Err(Error::UnexpectedProposalTxId.into());
         }
 
@@ -153,8 +161,13 @@ impl BtcAnchoringInterface<ExecutionContext<'_>> for BtcAnchoringService {
         arg.verify_signature(&input_signer, &public_key, &proposal, &expected_inputs)?;
 
         // All preconditions are correct and we can use this signature.
-        let input_id = TxInputId::new(proposal.id(), arg.input);
+        let input_id = TxInputId::new(proposal_id, arg.input);
         let mut input_signatures = schema.input_signatures(&input_id);
+        // Check that this anchoring node has not signed this input yet. Exact duplicates
+        // are rejected by the core, so the repeated signature can only be a different one.
+        if input_signatures.contains(anchoring_node_id) {
+            return Err(Error::InputAlreadySigned.into());
+        }
         let mut input_signature_len = input_signatures.len();
         // Check that we have not reached the quorum yet, otherwise we should not do anything.
         if input_signature_len < quorum {
@@ -174,7 +187,7 @@ impl BtcAnchoringInterface<ExecutionContext<'_>> for BtcAnchoringService {
             let mut finalized_tx: btc::Transaction = proposal.clone();
             // Make sure we reach a quorum for each input.
             for index in 0..expected_inputs.len() {
-                let input_id = TxInputId::new(proposal.id(), index as u32);
+                let input_id = TxInputId::new(proposal_id, index as u32);
                 let signatures_for_input = schema.input_signatures(&input_id);
                 // We have not enough signatures for this input, so we can not finalize this
                 // proposal at the moment.
@@ -191,7 +204,8 @@ impl BtcAnchoringInterface<ExecutionContext<'_>> for BtcAnchoringService {
             let payload = finalized_tx.anchoring_metadata().unwrap().1;
 
             info!("====== ANCHORING ======");
-            info!("txid: {}", finalized_tx.id().to_string());
+            // Witness data does not affect the transaction ID.
+            info!("txid: {}", proposal_id.to_string());
             info!("height: {}", payload.block_height);
             info!("hash: {}", payload.block_hash.to_hex());
             info!("balance: {}", finalized_tx.0.output[0].value);
@@ -239,7 +253,7 @@ impl BtcAnchoringInterface<ExecutionContext<'_>> for BtcAnchoringService {
         // otherwise just write confirmation to the schema.
         if confirmations.has_enough_confirmations(&actual_config)? {
             info!("====== ADD_FUNDS ======");
-            info!("txid: {}", arg.transaction.id().to_string());
+            info!("txid: {}", funding_txid.to_string());
             info!("balance: {}", txout.value);
 
             schema.set_funding_transaction(arg.transaction);
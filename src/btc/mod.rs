@@ -18,10 +18,19 @@ pub use btc_transaction_utils::test_data::{secp_gen_keypair, secp_gen_keypair_wi
 
 pub use self::{
     payload::Payload,
-    transaction::{BtcAnchoringTransactionBuilder, BuilderError, Transaction},
+    transaction::{
+        verify_anchoring_chain, BtcAnchoringTransactionBuilder, BuilderError, ChainError,
+        PolicyViolation, Transaction,
+    },
 };
 
-use bitcoin::{network::constants::Network, util::address};
+use bitcoin::{
+    network::constants::Network,
+    util::{
+        address,
+        bip32::{self, ChildNumber, DerivationPath},
+    },
+};
 use bitcoin_hashes::sha256d;
 use derive_more::{Display, From, FromStr, Into};
 use exonum_merkledb::{BinaryValue, ObjectHash};
@@ -43,6 +52,10 @@ pub struct PrivateKey(pub bitcoin::PrivateKey);
 #[derive(Debug, Clone, Copy, From, Into, PartialEq, Eq, PartialOrd, Ord, Hash, Display, FromStr)]
 pub struct PublicKey(pub bitcoin::PublicKey);
 
+/// BIP32 extended private key wrapper, from which the anchoring keys are derived.
+#[derive(Clone, From, Into, PartialEq, Eq)]
+pub struct ExtendedPrivateKey(pub bip32::ExtendedPrivKey);
+
 /// Bitcoin address wrapper.
 #[derive(Debug, Clone, From, Into, PartialEq, Eq, PartialOrd, Ord, Hash, Display, FromStr)]
 pub struct Address(pub address::Address);
@@ -77,6 +90,59 @@ impl std::fmt::Debug for PrivateKey {
     }
 }
 
+impl ToString for ExtendedPrivateKey {
+    fn to_string(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl std::str::FromStr for ExtendedPrivateKey {
+    type Err = bip32::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        bip32::ExtendedPrivKey::from_str(s).map(From::from)
+    }
+}
+
+impl std::fmt::Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        f.debug_struct("ExtendedPrivateKey")
+            .field("network", &self.0.network)
+            .finish()
+    }
+}
+
+impl ExtendedPrivateKey {
+    /// Creates a master extended private key from the given seed.
+    pub fn from_seed(network: Network, seed: &[u8]) -> Result<Self, bip32::Error> {
+        bip32::ExtendedPrivKey::new_master(network, seed).map(Self)
+    }
+
+    /// Returns the derivation path of the anchoring key with the given index.
+    ///
+    /// Anchoring keys use the BIP84 layout `m/84'/<coin>'/0'/0/<index>`,
+    /// where coin is `0` for the Bitcoin mainnet and `1` for the other networks.
+    pub fn derivation_path(network: Network, index: u32) -> DerivationPath {
+        let coin = if network == Network::Bitcoin { 0 } else { 1 };
+        DerivationPath::from(vec![
+            ChildNumber::Hardened { index: 84 },
+            ChildNumber::Hardened { index: coin },
+            ChildNumber::Hardened { index: 0 },
+            ChildNumber::Normal { index: 0 },
+            ChildNumber::Normal { index },
+        ])
+    }
+
+    /// Derives the anchoring keypair with the given index.
+    pub fn derive_keypair(&self, index: u32) -> Result<(PublicKey, PrivateKey), bip32::Error> {
+        let secp = bitcoin::secp256k1::Secp256k1::new();
+        let path = Self::derivation_path(self.0.network, index);
+        let private_key = self.0.derive_priv(&secp, &path)?.private_key;
+        let public_key = bitcoin::PublicKey::from_private_key(&secp, &private_key);
+        Ok((PublicKey(public_key), PrivateKey(private_key)))
+    }
+}
+
 impl FromHex for PublicKey {
     type Error = anyhow::Error;
 
@@ -213,6 +279,7 @@ impl From<Sha256d> for bitcoin::hash_types::Txid {
 impl_string_conversions_for_hex! { InputSignature }
 
 impl_serde_str! { PrivateKey }
+impl_serde_str! { ExtendedPrivateKey }
 impl_serde_str! { PublicKey }
 impl_serde_str! { Address }
 impl_serde_str! { InputSignature }
@@ -232,3 +299,143 @@ pub fn gen_keypair(network: Network) -> (PublicKey, PrivateKey) {
     let (pk, sk) = secp_gen_keypair(network);
     (PublicKey(pk), PrivateKey(sk))
 }
+
+/// Generates a new master extended private key from the random seed.
+pub fn gen_extended_key(network: Network) -> ExtendedPrivateKey {
+    let mut seed = [0_u8; 32];
+    rand::thread_rng().fill(&mut seed);
+    ExtendedPrivateKey::from_seed(network, &seed).expect("Seed has a correct length")
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        network::constants::Network,
+        util::{address::Payload, base58},
+    };
+
+    use super::{Address, ExtendedPrivateKey};
+
+    /// Converts the SLIP-132 extended private key, like `zprv` or `vprv`, into the BIP32 one
+    /// with the same key data by replacing its version bytes.
+    fn from_slip132(key: &str, network: Network) -> ExtendedPrivateKey {
+        let mut data = base58::from_check(key).unwrap();
+        let version: [u8; 4] = match network {
+            Network::Bitcoin => [0x04, 0x88, 0xad, 0xe4],
+            _ => [0x04, 0x35, 0x83, 0x94],
+        };
+        data[..4].copy_from_slice(&version);
+        base58::check_encode_slice(&data).parse().unwrap()
+    }
+
+    fn witness_version(address: &Address) -> u8 {
+        match address.0.payload {
+            Payload::WitnessProgram { version, .. } => version.to_u8(),
+            _ => panic!("Expected witness program address, got {}", address),
+        }
+    }
+
+    #[test]
+    fn address_witness_v0() {
+        let addresses = [
+            // P2WPKH
+            (
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+                Network::Bitcoin,
+            ),
+            // P2WSH
+            (
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+                Network::Testnet,
+            ),
+        ];
+
+        for &(s, network) in &addresses {
+            let address: Address = s.parse().unwrap();
+            assert_eq!(address.0.network, network);
+            assert_eq!(witness_version(&address), 0);
+            assert_eq!(address.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn address_witness_v1() {
+        let s = "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7k7grplx";
+
+        let address: Address = s.parse().unwrap();
+        assert_eq!(address.0.network, Network::Bitcoin);
+        assert_eq!(witness_version(&address), 1);
+        assert_eq!(address.to_string(), s);
+    }
+
+    #[test]
+    fn address_witness_serde() {
+        let s = "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7";
+        let address: Address = s.parse().unwrap();
+
+        let json = serde_json::to_value(&address).unwrap();
+        assert_eq!(json, serde_json::Value::String(s.to_owned()));
+        let address2: Address = serde_json::from_value(json).unwrap();
+        assert_eq!(address, address2);
+    }
+
+    #[test]
+    fn extended_key_derivation() {
+        // Seed from the BIP32 test vector 1.
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let xprv = ExtendedPrivateKey::from_seed(Network::Bitcoin, &seed).unwrap();
+        assert_eq!(
+            xprv.0.private_key.key.to_string(),
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+        );
+        assert_eq!(
+            xprv.to_string().parse::<ExtendedPrivateKey>().unwrap(),
+            xprv
+        );
+
+        let (pk0, sk0) = xprv.derive_keypair(0).unwrap();
+        let (pk1, _) = xprv.derive_keypair(1).unwrap();
+        assert_eq!(xprv.derive_keypair(0).unwrap().0, pk0);
+        assert_ne!(pk0, pk1);
+        assert_eq!(sk0.0.network, Network::Bitcoin);
+        assert_eq!(
+            ExtendedPrivateKey::derivation_path(Network::Testnet, 5),
+            "m/84'/1'/0'/0/5".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn extended_key_derivation_bip84_vectors() {
+        // Root keys of the "abandon abandon ... about" mnemonic from the BIP84 test vectors.
+        let vectors = [
+            (
+                "zprvAWgYBBk7JR8Gjrh4UJQ2uJdG1r3WNRRfURiABBE3RvMXYSrRJL62XuezvGdPvG6GFBZduosCc1YP\
+                 5wixPox7zhZLfiUm8aunE96BBa4Kei5",
+                Network::Bitcoin,
+                "0330d54fd0dd420a6e5f8d3624f5f3482cae350f79d5f0753bf5beef9c2d91af3c",
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            ),
+            (
+                "vprv9DMUxX4ShgxMLfvb8sFY4xFFKyTibwTfoydH3beVutr1L3bWHhRn3f2SqSo3vdUacd6QuuUxmN8B\
+                 YoGhX2J4okpwCMh4nwdq9EqbdGgioRF",
+                Network::Testnet,
+                "02e7ab2537b5d49e970309aae06e9e49f36ce1c9febbd44ec8e0d1cca0b4f9c319",
+                "tb1q6rz28mcfaxtmd6v789l9rrlrusdprr9pqcpvkl",
+            ),
+        ];
+
+        for &(root_key, network, public_key, address) in &vectors {
+            let xprv = from_slip132(root_key, network);
+            assert_eq!(xprv.0.network, network);
+
+            // The first receiving address at `m/84'/<coin>'/0'/0/0`.
+            let (pk, sk) = xprv.derive_keypair(0).unwrap();
+            assert_eq!(pk.to_string(), public_key);
+            assert_eq!(sk.0.network, network);
+            assert_eq!(
+                bitcoin::Address::p2wpkh(&pk.0, network).to_string(),
+                address
+            );
+        }
+    }
+}
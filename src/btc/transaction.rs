@@ -13,7 +13,7 @@ use bitcoin::blockdata::{
     script::Script,
     transaction::{self, OutPoint, TxIn, TxOut},
 };
-use btc_transaction_utils::multisig::RedeemScript;
+use btc_transaction_utils::{multisig::RedeemScript, p2wsh, InputSignatureRef, TxInRef};
 use derive_more::{From, Into};
 use thiserror::Error;
 
@@ -70,17 +70,124 @@ impl Transaction {
     pub fn unspent_value(&self) -> Option<u64> {
         self.0.output.get(0).map(|out| out.value)
     }
+
+    /// Returns the size of the transaction without witness data in bytes.
+    /// The anchoring transaction fee is computed from this size.
+    pub fn unsigned_size(&self) -> u64 {
+        let mut transaction = self.0.clone();
+        for input in &mut transaction.input {
+            input.witness.clear();
+        }
+        ::bitcoin::consensus::serialize(&transaction).len() as u64
+    }
+
+    /// Returns the estimated virtual size in bytes of this transaction after all of
+    /// its inputs are signed by the given redeem script with the maximum-length
+    /// signatures. The actual size may be a few bytes less.
+    pub fn signed_vsize_estimate(&self, redeem_script: &RedeemScript) -> u64 {
+        // Maximum length of a DER-encoded signature with the sighash type byte.
+        const MAX_SIGNATURE_LEN: u64 = 73;
+
+        let quorum = redeem_script.content().quorum as u64;
+        let script_len = redeem_script.as_ref().len() as u64;
+        // Witness stack is `[<empty>, <signatures>..., <redeem script>]`.
+        let witness_len = ::bitcoin::consensus::encode::VarInt(quorum + 2).len() as u64
+            + 1
+            + quorum * (1 + MAX_SIGNATURE_LEN)
+            + ::bitcoin::consensus::encode::VarInt(script_len).len() as u64
+            + script_len;
+        // Segwit marker and flag bytes.
+        let witness_data_len = 2 + witness_len * self.0.input.len() as u64;
+
+        let weight = self.unsigned_size() * 4 + witness_data_len;
+        (weight + 3) / 4
+    }
+
+    /// Checks that the signed anchoring transaction satisfies the standard relay policy
+    /// of the Bitcoin nodes, so it will not be rejected on broadcast.
+    ///
+    /// The anchoring transaction builder enforces the same policy for the proposals,
+    /// so this check only guards against the transactions created by other means.
+    pub fn check_policy(&self) -> Result<(), PolicyViolation> {
+        PolicyViolation::check_weight(self.0.get_weight() as u64)?;
+        PolicyViolation::check_payload_size(&self.0.output)
+    }
+}
+
+/// Violations of the standard relay policy of the Bitcoin nodes.
+#[derive(Debug, Copy, Clone, PartialEq, Error)]
+pub enum PolicyViolation {
+    /// Transaction weight exceeds the standard limit.
+    #[error("Transaction weight {weight} exceeds the standard limit of 400000")]
+    TooHeavy {
+        /// Transaction weight.
+        weight: u64,
+    },
+    /// Transaction has no payload output.
+    #[error("Transaction has no payload output")]
+    NoPayloadOutput,
+    /// Payload output script exceeds the standard `OP_RETURN` size limit.
+    #[error("Payload output script size {size} exceeds the standard limit of 83 bytes")]
+    PayloadTooLarge {
+        /// Payload output script size in bytes.
+        size: u64,
+    },
+    /// Transaction fee exceeds the default `maxfeerate` limit of the Bitcoin node
+    /// of 10000 satoshis per virtual byte.
+    #[error("Transaction fee {fee} exceeds the sanity limit of {max_fee} satoshis")]
+    FeeTooHigh {
+        /// Transaction fee in satoshis.
+        fee: u64,
+        /// Maximum fee for the transaction size in satoshis.
+        max_fee: u64,
+    },
+}
+
+impl PolicyViolation {
+    /// Maximum weight of the standard transaction.
+    const MAX_STANDARD_TX_WEIGHT: u64 = 400_000;
+    /// Maximum size of the standard `OP_RETURN` output script.
+    const MAX_OP_RETURN_RELAY: u64 = 83;
+    /// Maximum fee rate in satoshis per virtual byte accepted by `sendrawtransaction`
+    /// of the Bitcoin node by default.
+    const MAX_FEE_RATE: u64 = 10_000;
+
+    fn check_weight(weight: u64) -> Result<(), Self> {
+        if weight > Self::MAX_STANDARD_TX_WEIGHT {
+            return Err(PolicyViolation::TooHeavy { weight });
+        }
+        Ok(())
+    }
+
+    fn check_payload_size(outputs: &[TxOut]) -> Result<(), Self> {
+        let payload_output = outputs.get(1).ok_or(PolicyViolation::NoPayloadOutput)?;
+        let size = payload_output.script_pubkey.len() as u64;
+        if size > Self::MAX_OP_RETURN_RELAY {
+            return Err(PolicyViolation::PayloadTooLarge { size });
+        }
+        Ok(())
+    }
+
+    fn check_fee(fee: u64, vsize: u64) -> Result<(), Self> {
+        let max_fee = vsize * Self::MAX_FEE_RATE;
+        if fee > max_fee {
+            return Err(PolicyViolation::FeeTooHigh { fee, max_fee });
+        }
+        Ok(())
+    }
 }
 
 /// Builder for the anchoring transactions.
 #[derive(Debug)]
 pub struct BtcAnchoringTransactionBuilder {
+    redeem_script: RedeemScript,
     script_pubkey: Script,
     transit_to: Option<Script>,
     prev_tx: Option<Transaction>,
     recovery_tx: Option<Sha256d>,
     additional_funds: Vec<(usize, Transaction)>,
     fee: Option<u64>,
+    replaceable: bool,
     payload: Option<(Height, Hash)>,
 }
 
@@ -98,6 +205,18 @@ pub enum BuilderError {
         /// Available balance.
         balance: u64,
     },
+    /// Anchoring output value after paying the fee would be below the dust limit,
+    /// so the transaction would be rejected by the Bitcoin network.
+    #[error(
+        "Anchoring output value would be below the dust limit, \
+         total fee is {total_fee}, total balance is {balance}"
+    )]
+    DustOutput {
+        /// Total transaction fee.
+        total_fee: u64,
+        /// Available balance.
+        balance: u64,
+    },
     /// At least one input should be provided.
     #[error("At least one input should be provided.")]
     NoInputs,
@@ -107,18 +226,29 @@ pub enum BuilderError {
     /// Funding transaction doesn't contains outputs to the anchoring address.
     #[error("Funding transaction doesn't contains outputs to the anchoring address.")]
     UnsuitableFundingTx,
+    /// Anchoring transaction would violate the standard relay policy of the Bitcoin nodes.
+    #[error("Anchoring transaction would violate the standard relay policy: {0}")]
+    PolicyViolation(PolicyViolation),
 }
 
 impl BtcAnchoringTransactionBuilder {
+    /// Minimal value of the anchoring output in satoshis, which is not considered dust
+    /// by the Bitcoin nodes with the default relay policy.
+    pub const DUST_LIMIT: u64 = 330;
+    /// Input sequence number, which signals the BIP125 opt-in replaceability.
+    const REPLACEABLE_SEQUENCE: u32 = 0xFFFF_FFFD;
+
     /// Creates a new btc anchoring transaction builder for the given redeem script.
     pub fn new(redeem_script: &RedeemScript) -> BtcAnchoringTransactionBuilder {
         Self {
+            redeem_script: redeem_script.clone(),
             script_pubkey: redeem_script.as_ref().to_v0_p2wsh(),
             transit_to: None,
             prev_tx: None,
             recovery_tx: None,
             additional_funds: Vec::default(),
             fee: None,
+            replaceable: false,
             payload: None,
         }
     }
@@ -161,6 +291,11 @@ impl BtcAnchoringTransactionBuilder {
         self.fee = Some(fee);
     }
 
+    /// Sets whether the anchoring transaction signals the BIP125 opt-in replaceability.
+    pub fn replaceable(&mut self, replaceable: bool) {
+        self.replaceable = replaceable;
+    }
+
     /// Sets the anchoring transaction payload.
     pub fn payload(&mut self, block_height: Height, block_hash: Hash) {
         self.payload = Some((block_height, block_hash));
@@ -170,6 +305,11 @@ impl BtcAnchoringTransactionBuilder {
     /// it with the list of input transactions.
     pub fn create(mut self) -> Result<(Transaction, Vec<Transaction>), BuilderError> {
         // Creates transaction inputs.
+        let sequence = if self.replaceable {
+            Self::REPLACEABLE_SEQUENCE
+        } else {
+            0xFFFF_FFFF
+        };
         let (input, input_transactions, balance) = {
             let mut input = Vec::new();
             let mut input_transactions = Vec::new();
@@ -187,7 +327,7 @@ impl BtcAnchoringTransactionBuilder {
                         vout: out_index as u32,
                     },
                     script_sig: Script::default(),
-                    sequence: 0xFFFF_FFFF,
+                    sequence,
                     witness: Vec::default(),
                 };
                 balance += tx.0.output[out_index].value;
@@ -240,12 +380,282 @@ impl BtcAnchoringTransactionBuilder {
         if total_fee > balance {
             return Err(BuilderError::InsufficientFunds { total_fee, balance });
         }
+        // The anchoring output cannot be dropped, since it continues the anchoring chain.
+        if balance - total_fee < Self::DUST_LIMIT {
+            return Err(BuilderError::DustOutput { total_fee, balance });
+        }
+        // Check that the transaction will be relayed by the Bitcoin nodes after signing.
+        let vsize = transaction.signed_vsize_estimate(&self.redeem_script);
+        PolicyViolation::check_weight(vsize * 4)
+            .and_then(|_| PolicyViolation::check_payload_size(&transaction.0.output))
+            .and_then(|_| PolicyViolation::check_fee(total_fee, vsize))
+            .map_err(BuilderError::PolicyViolation)?;
         // Set the corresponding fee.
         transaction.0.output[0].value -= total_fee;
         Ok((transaction, input_transactions))
     }
 }
 
+/// Anchoring transactions chain verification errors.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ChainError {
+    /// Transaction does not contain the anchoring payload.
+    #[error("Transaction #{index} ({txid}) has no anchoring payload")]
+    MissingPayload {
+        /// Index of the transaction in the chain.
+        index: u64,
+        /// Transaction identifier.
+        txid: Sha256d,
+    },
+    /// Transaction has no inputs.
+    #[error("Transaction #{index} ({txid}) has no inputs")]
+    NoInputs {
+        /// Index of the transaction in the chain.
+        index: u64,
+        /// Transaction identifier.
+        txid: Sha256d,
+    },
+    /// Transaction has no outputs.
+    #[error("Transaction #{index} ({txid}) has no outputs")]
+    NoOutputs {
+        /// Index of the transaction in the chain.
+        index: u64,
+        /// Transaction identifier.
+        txid: Sha256d,
+    },
+    /// Anchoring output of the transaction is not a P2WSH output.
+    #[error("Transaction #{index} ({txid}) has an unsuitable anchoring output")]
+    UnsuitableOutput {
+        /// Index of the transaction in the chain.
+        index: u64,
+        /// Transaction identifier.
+        txid: Sha256d,
+    },
+    /// Transaction does not spend the previous anchoring transaction.
+    #[error(
+        "Transaction #{index} ({txid}) does not spend \
+         the previous anchoring transaction {prev_txid}"
+    )]
+    BrokenLink {
+        /// Index of the transaction in the chain.
+        index: u64,
+        /// Transaction identifier.
+        txid: Sha256d,
+        /// Identifier of the previous anchoring transaction.
+        prev_txid: Sha256d,
+    },
+    /// Anchored block height is lower than the one in the previous transaction.
+    #[error(
+        "Transaction #{index} ({txid}) anchors height {height}, \
+         which is lower than the previous one {prev_height}"
+    )]
+    DecreasingHeight {
+        /// Index of the transaction in the chain.
+        index: u64,
+        /// Transaction identifier.
+        txid: Sha256d,
+        /// Anchored block height.
+        height: Height,
+        /// Block height anchored by the previous transaction.
+        prev_height: Height,
+    },
+    /// Transaction input spends neither the previous anchoring transaction
+    /// nor a funding transaction.
+    #[error("Transaction #{index} ({txid}) input {input} spends unknown transaction {spent_txid}")]
+    UnknownInput {
+        /// Index of the transaction in the chain.
+        index: u64,
+        /// Transaction identifier.
+        txid: Sha256d,
+        /// Index of the input.
+        input: usize,
+        /// Identifier of the spent transaction.
+        spent_txid: Sha256d,
+    },
+    /// Transaction input witness does not unlock the spent output.
+    #[error("Transaction #{index} ({txid}) input {input} {reason}")]
+    InvalidWitness {
+        /// Index of the transaction in the chain.
+        index: u64,
+        /// Transaction identifier.
+        txid: Sha256d,
+        /// Index of the input.
+        input: usize,
+        /// Description of the problem.
+        reason: String,
+    },
+}
+
+/// Verifies the consistency of the given anchoring transactions chain.
+///
+/// Each transaction should contain the anchoring payload and spend the previous
+/// transaction, unless its payload refers to the lost previous chain. Anchored heights
+/// should not decrease. The witness of each input should contain the quorum of valid
+/// signatures for the redeem script of the spent P2WSH output.
+///
+/// The `funding_transaction` closure returns the spent funding transaction with the given
+/// identifier, for example, [`Schema::spent_funding_transaction`].
+///
+/// The check does not stop at the first problem, all found problems are returned
+/// in the order of the transactions in the chain.
+///
+/// [`Schema::spent_funding_transaction`]: ../blockchain/struct.Schema.html#method.spent_funding_transaction
+pub fn verify_anchoring_chain<I, F>(
+    transactions: I,
+    mut funding_transaction: F,
+) -> Result<(), Vec<ChainError>>
+where
+    I: IntoIterator<Item = Transaction>,
+    F: FnMut(&Sha256d) -> Option<Transaction>,
+{
+    let mut errors = Vec::new();
+    let mut prev: Option<(Transaction, Option<Height>)> = None;
+    for (index, tx) in transactions.into_iter().enumerate() {
+        let index = index as u64;
+        let txid = tx.id();
+        let payload = tx.anchoring_payload();
+        if payload.is_none() {
+            errors.push(ChainError::MissingPayload { index, txid });
+        }
+        // Transactions may come from a corrupted or external source,
+        // so their structure is not taken for granted.
+        match tx.0.output.first() {
+            Some(output) if !output.script_pubkey.is_v0_p2wsh() => {
+                errors.push(ChainError::UnsuitableOutput { index, txid });
+            }
+            Some(_) => {}
+            None => errors.push(ChainError::NoOutputs { index, txid }),
+        }
+        let first_input = tx.0.input.first();
+        if first_input.is_none() {
+            errors.push(ChainError::NoInputs { index, txid });
+        }
+
+        if let (Some((prev_tx, prev_height)), Some(payload)) = (&prev, &payload) {
+            let spends_prev_tx = first_input.map_or(false, |txin| {
+                Sha256d::from(txin.previous_output.txid) == prev_tx.id()
+            });
+            if payload.prev_tx_chain.is_none() && !spends_prev_tx {
+                errors.push(ChainError::BrokenLink {
+                    index,
+                    txid,
+                    prev_txid: prev_tx.id(),
+                });
+            }
+            // Transition transactions repeat the height of the previous anchor.
+            match prev_height {
+                Some(prev_height) if payload.block_height < *prev_height => {
+                    errors.push(ChainError::DecreasingHeight {
+                        index,
+                        txid,
+                        height: payload.block_height,
+                        prev_height: *prev_height,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        for (input, txin) in tx.0.input.iter().enumerate() {
+            let spent_txid: Sha256d = txin.previous_output.txid.into();
+            let spent_tx = match &prev {
+                Some((prev_tx, _)) if prev_tx.id() == spent_txid => Some(prev_tx.clone()),
+                _ => funding_transaction(&spent_txid),
+            };
+            let spent_tx = match spent_tx {
+                Some(spent_tx) => spent_tx,
+                None => {
+                    errors.push(ChainError::UnknownInput {
+                        index,
+                        txid,
+                        input,
+                        spent_txid,
+                    });
+                    continue;
+                }
+            };
+            if let Err(reason) = verify_input_witness(&tx, input, &spent_tx) {
+                errors.push(ChainError::InvalidWitness {
+                    index,
+                    txid,
+                    input,
+                    reason,
+                });
+            }
+        }
+
+        let height = payload.map(|payload| payload.block_height);
+        prev = Some((tx, height));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Checks the witness of the given transaction input against the output it spends.
+fn verify_input_witness(
+    tx: &Transaction,
+    input: usize,
+    spent_tx: &Transaction,
+) -> Result<(), String> {
+    let txin = &tx.0.input[input];
+    let spent_output = spent_tx
+        .0
+        .output
+        .get(txin.previous_output.vout as usize)
+        .ok_or_else(|| "spends nonexistent output".to_owned())?;
+
+    let script_bytes = txin
+        .witness
+        .last()
+        .ok_or_else(|| "has an empty witness".to_owned())?;
+    let redeem_script = RedeemScript::from_script(script_bytes.clone().into())
+        .map_err(|e| format!("has an incorrect redeem script: {}", e))?;
+    if redeem_script.as_ref().to_v0_p2wsh() != spent_output.script_pubkey {
+        return Err("redeem script does not match the spent output".to_owned());
+    }
+
+    // Witness layout is `[<empty>, <signatures>..., <redeem script>]`.
+    let signatures = txin
+        .witness
+        .get(1..txin.witness.len() - 1)
+        .unwrap_or_default();
+    let content = redeem_script.content();
+    if signatures.len() != content.quorum {
+        return Err(format!(
+            "has {} signatures, but quorum is {}",
+            signatures.len(),
+            content.quorum
+        ));
+    }
+
+    // Signatures follow the order of the public keys in the redeem script,
+    // as `OP_CHECKMULTISIG` requires.
+    let signer = p2wsh::InputSigner::new(redeem_script);
+    let mut public_keys = content.public_keys.iter();
+    for (i, signature) in signatures.iter().enumerate() {
+        let signature = InputSignatureRef::from_bytes(signature)
+            .map_err(|e| format!("has a malformed signature #{}: {}", i, e))?;
+        let is_valid = public_keys.by_ref().any(|public_key| {
+            signer
+                .verify_input(
+                    TxInRef::new(tx.as_ref(), input),
+                    spent_tx.as_ref(),
+                    public_key,
+                    signature,
+                )
+                .is_ok()
+        });
+        if !is_valid {
+            return Err(format!("has an invalid signature #{}", i));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use exonum::crypto::Hash;
@@ -263,7 +673,7 @@ mod tests {
     };
 
     use bitcoin_hashes::{sha256d::Hash as Sha256dHash, Hash as BitcoinHash};
-    use btc_transaction_utils::multisig::RedeemScriptBuilder;
+    use btc_transaction_utils::multisig::{RedeemScript, RedeemScriptBuilder};
     use hex::FromHex;
     use proptest::proptest;
 
@@ -271,7 +681,7 @@ mod tests {
 
     use crate::btc::PublicKey;
 
-    use super::{BtcAnchoringTransactionBuilder, BuilderError, Transaction};
+    use super::{BtcAnchoringTransactionBuilder, BuilderError, PolicyViolation, Transaction};
 
     #[test]
     fn test_transaction_conversions() {
@@ -412,8 +822,9 @@ mod tests {
         let _ = <Transaction as BinaryValue>::from_bytes(Cow::Borrowed(&tx_raw)).unwrap();
     }
 
-    #[test]
-    fn test_anchoring_transaction_builder_simple() {
+    /// Returns a funding transaction with 10000 satoshis sent to the multisig
+    /// address and the corresponding redeem script.
+    fn sample_funding_and_redeem_script() -> (Transaction, RedeemScript) {
         let funding_tx: Transaction = Transaction::from_hex(
             "02000000000101b651818fe3855d0d5d74de1cf72b56503c16f808519440e842b6\
              dc2dd570c4930100000000feffffff02deaa7b0000000000160014923904449829\
@@ -438,12 +849,19 @@ mod tests {
         let redeem_script = RedeemScriptBuilder::with_public_keys(keys)
             .to_script()
             .unwrap();
+        (funding_tx, redeem_script)
+    }
+
+    #[test]
+    fn test_anchoring_transaction_builder_simple() {
+        let (funding_tx, redeem_script) = sample_funding_and_redeem_script();
 
         let mut builder = BtcAnchoringTransactionBuilder::new(&redeem_script);
         builder.additional_funds(funding_tx.clone()).unwrap();
         builder.fee(1);
         builder.payload(Height::zero(), funding_tx.object_hash());
         let (tx, inputs) = builder.create().unwrap();
+        let tx_size = tx.unsigned_size();
 
         assert_eq!(funding_tx, inputs[0]);
         assert_eq!(tx.0.version, 2);
@@ -459,6 +877,81 @@ mod tests {
 
         assert_ne!(out_0.value, 0);
         assert_eq!(out_1.value, 0);
+        // Fee per byte is equal to one satoshi.
+        assert_eq!(out_0.value, 10_000 - tx_size);
+    }
+
+    #[test]
+    fn test_anchoring_transaction_builder_dust_output() {
+        let (funding_tx, redeem_script) = sample_funding_and_redeem_script();
+
+        let mut builder = BtcAnchoringTransactionBuilder::new(&redeem_script);
+        builder.additional_funds(funding_tx.clone()).unwrap();
+        // Transaction size is 153 bytes, so only 208 satoshis remain after paying the fee.
+        builder.fee(64);
+        builder.payload(Height::zero(), funding_tx.object_hash());
+        assert_eq!(
+            builder.create().unwrap_err(),
+            BuilderError::DustOutput {
+                total_fee: 9792,
+                balance: 10_000
+            }
+        );
+    }
+
+    #[test]
+    fn test_anchoring_transaction_builder_replaceable() {
+        let (funding_tx, redeem_script) = sample_funding_and_redeem_script();
+
+        for &(replaceable, sequence) in &[(false, 0xFFFF_FFFF), (true, 0xFFFF_FFFD)] {
+            let mut builder = BtcAnchoringTransactionBuilder::new(&redeem_script);
+            builder.additional_funds(funding_tx.clone()).unwrap();
+            builder.fee(1);
+            builder.replaceable(replaceable);
+            builder.payload(Height::zero(), funding_tx.object_hash());
+
+            let (tx, _) = builder.create().unwrap();
+            assert!(tx.0.input.iter().all(|input| input.sequence == sequence));
+        }
+    }
+
+    #[test]
+    fn test_anchoring_transaction_policy() {
+        let (funding_tx, redeem_script) = sample_funding_and_redeem_script();
+
+        let mut builder = BtcAnchoringTransactionBuilder::new(&redeem_script);
+        builder.additional_funds(funding_tx.clone()).unwrap();
+        builder.fee(1);
+        builder.payload(Height::zero(), funding_tx.object_hash());
+        let (tx, _) = builder.create().unwrap();
+        assert_eq!(tx.check_policy(), Ok(()));
+
+        let mut large_payload_tx = tx;
+        large_payload_tx.0.output[1].script_pubkey = Builder::new()
+            .push_opcode(OP_RETURN)
+            .push_slice(&[0; 100])
+            .into_script();
+        assert_eq!(
+            large_payload_tx.check_policy(),
+            Err(PolicyViolation::PayloadTooLarge { size: 103 })
+        );
+
+        let mut no_payload_tx = large_payload_tx;
+        no_payload_tx.0.output.truncate(1);
+        assert_eq!(
+            no_payload_tx.check_policy(),
+            Err(PolicyViolation::NoPayloadOutput)
+        );
+
+        // The fee rate exceeds the sanity limit.
+        assert_eq!(PolicyViolation::check_fee(1_000, 200), Ok(()));
+        assert_eq!(
+            PolicyViolation::check_fee(2_000_001, 200),
+            Err(PolicyViolation::FeeTooHigh {
+                fee: 2_000_001,
+                max_fee: 2_000_000
+            })
+        );
     }
 
     #[test]
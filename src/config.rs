@@ -36,13 +36,14 @@ impl Default for Config {
             anchoring_keys: vec![],
             anchoring_interval: 5_000,
             transaction_fee: 10,
+            replaceable: false,
         }
     }
 }
 
 impl Config {
     /// Current limit on the number of keys in a redeem script on the Bitcoin network.
-    const MAX_NODES_COUNT: usize = 20;
+    pub(crate) const MAX_NODES_COUNT: usize = 20;
     /// Minimal fee in satoshis for Bitcoin transaction.
     const MIN_TOTAL_TX_FEE: u64 = 1000;
     /// Minimal total transaction size according to
@@ -87,10 +88,7 @@ impl Config {
 
     /// Returns the corresponding redeem script.
     pub fn redeem_script(&self) -> RedeemScript {
-        RedeemScriptBuilder::with_public_keys(self.anchoring_keys.iter().map(|x| x.bitcoin_key.0))
-            .quorum(self.byzantine_quorum())
-            .to_script()
-            .unwrap()
+        derive_redeem_script(self.anchoring_keys.iter().map(|x| x.bitcoin_key), None).unwrap()
     }
 
     /// Computes the P2WSH output corresponding to the actual redeem script.
@@ -114,6 +112,21 @@ impl Config {
     }
 }
 
+/// Derives the anchoring redeem script for the given Bitcoin public keys.
+///
+/// If the quorum is not specified, the byzantine quorum for the given number of keys
+/// is used, just like in the anchoring configuration.
+pub fn derive_redeem_script(
+    bitcoin_keys: impl IntoIterator<Item = btc::PublicKey>,
+    quorum: Option<usize>,
+) -> Result<RedeemScript, RedeemScriptError> {
+    let bitcoin_keys = bitcoin_keys.into_iter().map(|x| x.0).collect::<Vec<_>>();
+    let quorum = quorum.unwrap_or_else(|| exonum::helpers::byzantine_quorum(bitcoin_keys.len()));
+    RedeemScriptBuilder::with_public_keys(bitcoin_keys)
+        .quorum(quorum)
+        .to_script()
+}
+
 impl ValidateInput for Config {
     type Error = anyhow::Error;
 
@@ -157,7 +170,7 @@ mod tests {
 
     use crate::proto::AnchoringKeys;
 
-    use super::Config;
+    use super::{derive_redeem_script, Config};
 
     fn gen_anchoring_keys(network: bitcoin::Network, count: usize) -> Vec<AnchoringKeys> {
         (0..count)
@@ -206,6 +219,25 @@ mod tests {
 
     // TODO test validation of the Bitcoin anchoring config
 
+    #[test]
+    fn config_derive_redeem_script() {
+        let public_keys = gen_anchoring_keys(Network::Testnet, 4);
+        let bitcoin_keys = public_keys
+            .iter()
+            .map(|x| x.bitcoin_key)
+            .collect::<Vec<_>>();
+        let config = Config::with_public_keys(Network::Testnet, public_keys).unwrap();
+
+        let redeem_script = derive_redeem_script(bitcoin_keys.clone(), None).unwrap();
+        assert_eq!(redeem_script, config.redeem_script());
+
+        let redeem_script = derive_redeem_script(bitcoin_keys.clone(), Some(4)).unwrap();
+        assert_eq!(redeem_script.content().quorum, 4);
+        assert_ne!(redeem_script, config.redeem_script());
+
+        assert!(derive_redeem_script(bitcoin_keys, Some(5)).is_err());
+    }
+
     #[test]
     fn config_validate_errors() {
         let test_cases = [
@@ -69,4 +69,5 @@ pub mod test_helpers;
 
 pub(crate) mod service;
 
+mod migrations;
 mod proto;
@@ -0,0 +1,115 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Data migrations of the BTC anchoring service.
+
+use exonum::runtime::{
+    migrations::{
+        InitMigrationError, LinearMigrations, MigrateData, MigrationContext, MigrationError,
+        MigrationScript,
+    },
+    versioning::Version,
+};
+use exonum_merkledb::access::AccessExt;
+use exonum_rust_runtime::ServiceFactory;
+
+use crate::{blockchain::Schema, btc::Sha256d, BtcAnchoringService};
+
+/// Builds the index of the anchoring transactions by their identifiers and
+/// accumulates the paid fees for the chains created before version 1.1.0.
+fn migrate_to_1_1_0(context: &mut MigrationContext) -> Result<(), MigrationError> {
+    let old_schema = Schema::new(context.helper.old_data());
+    // Only the new indexes are touched, the rest of the service data stays as is.
+    let new_data = context.helper.new_data();
+    let mut transaction_indices = new_data.get_proof_map::<_, Sha256d, u64>("transaction_indices");
+
+    let mut total_fee = 0;
+    let mut known_fee_count = 0;
+    let mut prev_tx = None;
+    for (index, transaction) in old_schema.transactions_chain.iter().enumerate() {
+        transaction_indices.put(&transaction.id(), index as u64);
+        if let Some(fee) = old_schema.transaction_fee(prev_tx.as_ref(), &transaction) {
+            total_fee += fee;
+            known_fee_count += 1;
+        }
+        prev_tx = Some(transaction);
+    }
+    new_data.get_entry::<_, u64>("total_fee").set(total_fee);
+    new_data
+        .get_entry::<_, u64>("known_fee_count")
+        .set(known_fee_count);
+    Ok(())
+}
+
+impl MigrateData for BtcAnchoringService {
+    fn migration_scripts(
+        &self,
+        start_version: &Version,
+    ) -> Result<Vec<MigrationScript>, InitMigrationError> {
+        LinearMigrations::new(self.artifact_id().version)
+            .add_script(Version::new(1, 1, 0), migrate_to_1_1_0)
+            .select(start_version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum::{helpers::Height, runtime::versioning::Version};
+    use exonum_testkit::migrations::MigrationTest;
+
+    use crate::{
+        blockchain::Schema,
+        test_helpers::{get_anchoring_schema, AnchoringTestKit},
+        BtcAnchoringService,
+    };
+
+    #[test]
+    fn migration_to_1_1_0() {
+        let mut anchoring_testkit = AnchoringTestKit::default();
+        anchoring_testkit.create_blocks_with_anchoring_until(Height(11));
+        let transactions = get_anchoring_schema(&anchoring_testkit.inner.snapshot())
+            .transactions_chain
+            .iter()
+            .collect::<Vec<_>>();
+        assert_eq!(transactions.len(), 3);
+
+        let mut test = MigrationTest::new(BtcAnchoringService, Version::new(1, 0, 0));
+        let funding_transaction = get_anchoring_schema(&anchoring_testkit.inner.snapshot())
+            .spent_funding_transactions
+            .values()
+            .next()
+            .unwrap();
+        let setup_transactions = transactions.clone();
+        test.setup(move |access| {
+            // Emulate the data layout of version 1.0.0 without the new indexes.
+            let mut schema = Schema::new(access);
+            schema
+                .spent_funding_transactions
+                .put(&funding_transaction.id(), funding_transaction);
+            schema.transactions_chain.extend(setup_transactions);
+        });
+
+        let snapshot = test.migrate().end_snapshot();
+        let schema = Schema::new(snapshot);
+        assert_eq!(schema.transactions_chain.len(), 3);
+        for (index, transaction) in transactions.iter().enumerate() {
+            assert_eq!(
+                schema.transaction_index(&transaction.id()),
+                Some(index as u64)
+            );
+        }
+        assert_eq!(schema.total_fee.get(), Some(1530 * 3));
+        assert_eq!(schema.known_fee_count.get(), Some(3));
+    }
+}
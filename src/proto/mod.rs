@@ -145,6 +145,10 @@ pub struct Config {
     pub anchoring_interval: u64,
     /// Fee per byte in satoshis.
     pub transaction_fee: u64,
+    /// Whether anchoring transactions signal the BIP125 opt-in replaceability,
+    /// so that their fee can be bumped by the Bitcoin nodes policy.
+    #[serde(default)]
+    pub replaceable: bool,
 }
 
 impl ProtobufConvert for Config {
@@ -157,6 +161,7 @@ impl ProtobufConvert for Config {
         proto_struct.set_anchoring_keys(self.anchoring_keys.to_pb().into());
         proto_struct.set_anchoring_interval(self.anchoring_interval.to_pb());
         proto_struct.set_transaction_fee(self.transaction_fee.to_pb());
+        proto_struct.set_replaceable(self.replaceable);
         proto_struct
     }
 
@@ -169,6 +174,7 @@ impl ProtobufConvert for Config {
             anchoring_keys: ProtobufConvert::from_pb(pb.take_anchoring_keys().into_vec())?,
             anchoring_interval: ProtobufConvert::from_pb(pb.get_anchoring_interval())?,
             transaction_fee: ProtobufConvert::from_pb(pb.get_transaction_fee())?,
+            replaceable: pb.get_replaceable(),
         })
     }
 }
@@ -17,6 +17,15 @@
 use async_trait::async_trait;
 use bitcoincore_rpc::RpcApi;
 use jsonrpc::Error as JsonRpcError;
+use rand::{thread_rng, Rng};
+use tokio::time::delay_for;
+
+use std::{
+    cmp,
+    fmt::Display,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
 
 use crate::btc;
 
@@ -48,6 +57,61 @@ impl TransactionStatus {
     }
 }
 
+/// State of the Bitcoin network node behind the relay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitcoinNodeInfo {
+    /// Version of the Bitcoin node software in the `getnetworkinfo` format,
+    /// e.g. `180100` for version `0.18.1`, if it is known.
+    pub version: Option<u64>,
+    /// Height of the best known Bitcoin block.
+    pub blocks: u64,
+    /// Whether the node is in the initial block download state and has
+    /// an outdated view of the Bitcoin blockchain.
+    pub initial_block_download: bool,
+}
+
+/// Classification of the errors returned by the Bitcoin relays.
+pub trait RelayError {
+    /// Returns `true` if the request may succeed after a while, e.g. if the Bitcoin node
+    /// is temporarily unreachable, has not responded in time or is warming up.
+    fn is_transient(&self) -> bool;
+    /// Returns `true` if the error is caused by the communication with the Bitcoin node,
+    /// so another node may handle the same request.
+    fn is_transport(&self) -> bool;
+}
+
+/// Error code of the Bitcoin node RPC which is returned during the node startup.
+const RPC_IN_WARMUP: i32 = -28;
+/// Error code of the Bitcoin node RPC which is returned for the unknown transactions.
+const RPC_INVALID_ADDRESS_OR_KEY: i32 = -5;
+
+/// Returns the code of the error returned by the Bitcoin node RPC, if any.
+fn rpc_error_code(error: &bitcoincore_rpc::Error) -> Option<i32> {
+    if let bitcoincore_rpc::Error::JsonRpc(JsonRpcError::Rpc(e)) = error {
+        Some(e.code)
+    } else {
+        None
+    }
+}
+
+impl RelayError for bitcoincore_rpc::Error {
+    fn is_transient(&self) -> bool {
+        match self {
+            bitcoincore_rpc::Error::JsonRpc(JsonRpcError::Hyper(_))
+            | bitcoincore_rpc::Error::Io(_) => true,
+            bitcoincore_rpc::Error::JsonRpc(JsonRpcError::Rpc(e)) => e.code == RPC_IN_WARMUP,
+            _ => false,
+        }
+    }
+
+    fn is_transport(&self) -> bool {
+        matches!(
+            self,
+            bitcoincore_rpc::Error::JsonRpc(JsonRpcError::Hyper(_)) | bitcoincore_rpc::Error::Io(_)
+        )
+    }
+}
+
 /// Describes communication with the Bitcoin network node.
 #[async_trait]
 pub trait BitcoinRelay {
@@ -60,6 +124,11 @@ pub trait BitcoinRelay {
     ) -> Result<btc::Sha256d, Self::Error>;
     /// Gets status for the transaction with the specified identifier.
     async fn transaction_status(&self, id: btc::Sha256d) -> Result<TransactionStatus, Self::Error>;
+    /// Gets the state of the Bitcoin network node. Returns `None` if the relay
+    /// does not provide such information.
+    async fn health_check(&self) -> Result<Option<BitcoinNodeInfo>, Self::Error> {
+        Ok(None)
+    }
 }
 
 #[async_trait]
@@ -83,11 +152,684 @@ impl BitcoinRelay for bitcoincore_rpc::Client {
                 };
                 Ok(status)
             }
-            // TODO Write more graceful error handling. [ECR-3222]
-            Err(bitcoincore_rpc::Error::JsonRpc(JsonRpcError::Rpc(_))) => {
-                Ok(TransactionStatus::Unknown)
+            // Bitcoin nodes without the transaction index, including the pruned ones,
+            // are only aware of the mempool transactions.
+            Err(e) if rpc_error_code(&e) == Some(RPC_INVALID_ADDRESS_OR_KEY) => {
+                non_indexed_transaction_status(self, id)
             }
             Err(e) => Err(e),
         }
     }
+
+    async fn health_check(&self) -> Result<Option<BitcoinNodeInfo>, Self::Error> {
+        let blockchain_info = self.get_blockchain_info()?;
+        let network_info = self.get_network_info()?;
+        Ok(Some(BitcoinNodeInfo {
+            version: Some(network_info.version as u64),
+            blocks: blockchain_info.blocks,
+            initial_block_download: blockchain_info.initial_block_download,
+        }))
+    }
+}
+
+/// Gets the transaction status from the Bitcoin node wallet if the transaction
+/// index is unavailable.
+///
+/// The wallet knows about anchoring transactions only if the anchoring address is
+/// imported into it, so such Bitcoin nodes require the import. The UTXO set cannot
+/// be used instead, since it does not distinguish the anchoring transactions spent
+/// by the next ones from the unknown transactions. If the wallet is not loaded or
+/// disabled, the wallet error is returned.
+fn non_indexed_transaction_status(
+    client: &bitcoincore_rpc::Client,
+    id: btc::Sha256d,
+) -> Result<TransactionStatus, bitcoincore_rpc::Error> {
+    match client.get_transaction(&id.into(), Some(true)) {
+        Ok(info) => {
+            // Negative number of confirmations means that the transaction conflicts
+            // with the committed one.
+            let status = match info.info.confirmations {
+                0 => TransactionStatus::Mempool,
+                num if num > 0 => TransactionStatus::Committed(num as u32),
+                _ => TransactionStatus::Unknown,
+            };
+            Ok(status)
+        }
+        // The transaction is unknown to the wallet with the imported anchoring address.
+        Err(e) if rpc_error_code(&e) == Some(RPC_INVALID_ADDRESS_OR_KEY) => {
+            Ok(TransactionStatus::Unknown)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Bitcoin relay that combines several relays to the different Bitcoin nodes.
+///
+/// Requests are sent to the relay that has handled the previous request successfully.
+/// If it fails to communicate with its Bitcoin node, the request is sent to the next
+/// relays in turn until one of them succeeds. If all of the relays fail, the error
+/// of the last one is returned. Other errors, e.g. the rejection of the transaction
+/// by the Bitcoin node, are returned immediately.
+///
+/// The health check requests all of the relays and marks the Bitcoin nodes that are
+/// unreachable, in the initial block download state or behind the best known block
+/// by more than [`MAX_BLOCKS_LAG`] blocks as unhealthy. Such relays are requested
+/// only if all the healthy ones fail. The lowest version of the reachable nodes is
+/// reported.
+///
+/// [`MAX_BLOCKS_LAG`]: #associatedconstant.MAX_BLOCKS_LAG
+#[derive(Debug)]
+pub struct FailoverRelay<R> {
+    relays: Vec<R>,
+    current: AtomicUsize,
+    unhealthy: Vec<AtomicBool>,
+}
+
+impl<R> FailoverRelay<R> {
+    /// Maximum number of blocks by which the Bitcoin node may fall behind the best
+    /// known block to be considered healthy.
+    pub const MAX_BLOCKS_LAG: u64 = 3;
+
+    /// Creates a new failover relay with the given relays in order of their priority.
+    ///
+    /// # Panics
+    ///
+    /// - If the given list of relays is empty.
+    pub fn new(relays: Vec<R>) -> Self {
+        assert!(
+            !relays.is_empty(),
+            "Failover relay should contain at least one relay"
+        );
+        Self {
+            unhealthy: relays.iter().map(|_| AtomicBool::new(false)).collect(),
+            relays,
+            current: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns indices of relays in order in which they should be requested.
+    /// Unhealthy relays are moved to the end of the list.
+    fn relays_order(&self) -> Vec<usize> {
+        let current = self.current.load(Ordering::Relaxed);
+        let (healthy, unhealthy): (Vec<_>, Vec<_>) = (current..self.relays.len())
+            .chain(0..current)
+            .partition(|&index| !self.unhealthy[index].load(Ordering::Relaxed));
+        healthy.into_iter().chain(unhealthy).collect()
+    }
+
+    fn on_success(&self, index: usize) {
+        if self.current.swap(index, Ordering::Relaxed) != index {
+            log::info!("Switched to the Bitcoin relay #{}", index);
+        }
+    }
+}
+
+#[async_trait]
+impl<R> BitcoinRelay for FailoverRelay<R>
+where
+    R: BitcoinRelay + Send + Sync,
+    R::Error: RelayError + Display + Send,
+{
+    type Error = R::Error;
+
+    async fn send_transaction(
+        &self,
+        transaction: &btc::Transaction,
+    ) -> Result<btc::Sha256d, Self::Error> {
+        let mut last_error = None;
+        for index in self.relays_order() {
+            match self.relays[index].send_transaction(transaction).await {
+                Ok(id) => {
+                    self.on_success(index);
+                    return Ok(id);
+                }
+                Err(e) if !e.is_transport() => return Err(e),
+                Err(e) => {
+                    log::warn!("Bitcoin relay #{} failed to send transaction: {}", index, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        // The list of relays is never empty.
+        Err(last_error.unwrap())
+    }
+
+    async fn transaction_status(&self, id: btc::Sha256d) -> Result<TransactionStatus, Self::Error> {
+        let mut last_error = None;
+        for index in self.relays_order() {
+            match self.relays[index].transaction_status(id).await {
+                Ok(status) => {
+                    self.on_success(index);
+                    return Ok(status);
+                }
+                Err(e) if !e.is_transport() => return Err(e),
+                Err(e) => {
+                    log::warn!(
+                        "Bitcoin relay #{} failed to get transaction status: {}",
+                        index,
+                        e
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        // The list of relays is never empty.
+        Err(last_error.unwrap())
+    }
+
+    /// Checks the state of all the Bitcoin nodes and returns the state of the one
+    /// which will handle the next request. The reported version is the lowest one
+    /// among the reachable nodes, so that the version requirements are checked
+    /// against every node. The current relay is not changed here, the unhealthy
+    /// relays are skipped by the subsequent requests.
+    async fn health_check(&self) -> Result<Option<BitcoinNodeInfo>, Self::Error> {
+        let mut infos = Vec::with_capacity(self.relays.len());
+        let mut last_error = None;
+        for (index, relay) in self.relays.iter().enumerate() {
+            match relay.health_check().await {
+                Ok(info) => infos.push(Some(info)),
+                Err(e) => {
+                    log::warn!("Bitcoin relay #{} failed to check health: {}", index, e);
+                    infos.push(None);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        let best_height = infos
+            .iter()
+            .filter_map(|info| info.as_ref()?.as_ref())
+            .filter(|info| !info.initial_block_download)
+            .map(|info| info.blocks)
+            .max()
+            .unwrap_or_default();
+        for (index, info) in infos.iter().enumerate() {
+            let is_healthy = match info {
+                // The relay is unreachable.
+                None => false,
+                // The relay does not provide information about its node.
+                Some(None) => true,
+                Some(Some(info)) => {
+                    !info.initial_block_download
+                        && best_height.saturating_sub(info.blocks) <= Self::MAX_BLOCKS_LAG
+                }
+            };
+            let was_healthy = !self.unhealthy[index].swap(!is_healthy, Ordering::Relaxed);
+            if was_healthy && !is_healthy {
+                log::warn!("Bitcoin relay #{} is unhealthy, it will not be used", index);
+            } else if !was_healthy && is_healthy {
+                log::info!("Bitcoin relay #{} is healthy again", index);
+            }
+        }
+
+        let min_version = infos
+            .iter()
+            .filter_map(|info| info.as_ref()?.as_ref()?.version)
+            .min();
+        // Report the state of the relay which will handle the next request.
+        let info = self
+            .relays_order()
+            .into_iter()
+            .find_map(|index| infos[index].take());
+        match info {
+            Some(info) => Ok(info.map(|info| BitcoinNodeInfo {
+                version: min_version,
+                ..info
+            })),
+            // All the relays are unreachable, and the list of relays is never empty.
+            None => Err(last_error.unwrap()),
+        }
+    }
+}
+
+/// Bitcoin relay that retries failed requests with an exponential backoff.
+///
+/// Only the [transient](trait.RelayError.html#tymethod.is_transient) errors are retried,
+/// the other ones are returned immediately.
+///
+/// The delay before the next attempt is doubled after each failure, but never exceeds
+/// the maximum delay. A random part of the delay is subtracted from it to prevent
+/// several sync utilities from retrying simultaneously.
+#[derive(Debug)]
+pub struct RetryRelay<R> {
+    inner: R,
+    max_attempts: u32,
+    initial_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+}
+
+impl<R> RetryRelay<R> {
+    /// Default maximum number of attempts for a single request.
+    pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+    /// Default delay before the second attempt.
+    pub const DEFAULT_INITIAL_DELAY: Duration = Duration::from_millis(500);
+    /// Default maximum delay between attempts.
+    pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(10);
+    /// Default fraction of the delay which can be randomly subtracted from it.
+    pub const DEFAULT_JITTER: f64 = 0.5;
+
+    /// Creates a new retry relay on top of the given one with the default retry policy.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+            initial_delay: Self::DEFAULT_INITIAL_DELAY,
+            max_delay: Self::DEFAULT_MAX_DELAY,
+            jitter: Self::DEFAULT_JITTER,
+        }
+    }
+
+    /// Sets the maximum number of attempts for a single request, including the first one.
+    /// Value `1` disables retries.
+    ///
+    /// # Panics
+    ///
+    /// - If the given number of attempts is zero.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        assert!(max_attempts > 0, "Number of attempts should be positive");
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the delay before the second attempt and the maximum delay between attempts.
+    pub fn with_backoff(mut self, initial_delay: Duration, max_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the fraction of the delay which can be randomly subtracted from it.
+    ///
+    /// # Panics
+    ///
+    /// - If the given jitter is not in the `[0, 1]` range.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&jitter),
+            "Jitter should be in the [0, 1] range"
+        );
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the delay after the failed attempt with the given number, starting from zero.
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_delay
+            .checked_mul(1 << cmp::min(attempt, 16))
+            .map_or(self.max_delay, |delay| cmp::min(delay, self.max_delay));
+        backoff.mul_f64(1.0 - self.jitter * thread_rng().gen::<f64>())
+    }
+}
+
+#[async_trait]
+impl<R> BitcoinRelay for RetryRelay<R>
+where
+    R: BitcoinRelay + Send + Sync,
+    R::Error: RelayError + Display + Send,
+{
+    type Error = R::Error;
+
+    async fn send_transaction(
+        &self,
+        transaction: &btc::Transaction,
+    ) -> Result<btc::Sha256d, Self::Error> {
+        let mut attempt = 0;
+        loop {
+            match self.inner.send_transaction(transaction).await {
+                Err(e) if e.is_transient() && attempt + 1 < self.max_attempts => {
+                    let delay = self.delay(attempt);
+                    log::warn!("Failed to send transaction: {}, retrying in {:?}", e, delay);
+                    delay_for(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn transaction_status(&self, id: btc::Sha256d) -> Result<TransactionStatus, Self::Error> {
+        let mut attempt = 0;
+        loop {
+            match self.inner.transaction_status(id).await {
+                Err(e) if e.is_transient() && attempt + 1 < self.max_attempts => {
+                    let delay = self.delay(attempt);
+                    log::warn!(
+                        "Failed to get transaction status: {}, retrying in {:?}",
+                        e,
+                        delay
+                    );
+                    delay_for(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn health_check(&self) -> Result<Option<BitcoinNodeInfo>, Self::Error> {
+        // Health check should report the problem as soon as possible, so it is not retried.
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use jsonrpc::{error::RpcError, Error as JsonRpcError};
+
+    use std::{
+        fmt,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::{
+        rpc_error_code, BitcoinNodeInfo, BitcoinRelay, FailoverRelay, RelayError, RetryRelay,
+        TransactionStatus, RPC_INVALID_ADDRESS_OR_KEY, RPC_IN_WARMUP,
+    };
+    use crate::btc;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum FakeError {
+        Offline,
+        Rejected,
+    }
+
+    impl fmt::Display for FakeError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt::Debug::fmt(self, f)
+        }
+    }
+
+    impl RelayError for FakeError {
+        fn is_transient(&self) -> bool {
+            *self == FakeError::Offline
+        }
+
+        fn is_transport(&self) -> bool {
+            *self == FakeError::Offline
+        }
+    }
+
+    /// Relay that counts requests and fails the given number of first requests.
+    #[derive(Debug)]
+    struct FakeRelay {
+        failures: usize,
+        error: FakeError,
+        requests: AtomicUsize,
+        info: Option<BitcoinNodeInfo>,
+    }
+
+    impl Default for FakeRelay {
+        fn default() -> Self {
+            Self::with_failures(0)
+        }
+    }
+
+    impl FakeRelay {
+        fn offline() -> Self {
+            Self::with_failures(usize::max_value())
+        }
+
+        fn with_failures(failures: usize) -> Self {
+            Self {
+                failures,
+                error: FakeError::Offline,
+                requests: AtomicUsize::default(),
+                info: None,
+            }
+        }
+
+        fn with_node_info(blocks: u64, initial_block_download: bool) -> Self {
+            Self {
+                info: Some(BitcoinNodeInfo {
+                    version: None,
+                    blocks,
+                    initial_block_download,
+                }),
+                ..Self::default()
+            }
+        }
+
+        fn with_version(version: u64) -> Self {
+            Self {
+                info: Some(BitcoinNodeInfo {
+                    version: Some(version),
+                    blocks: 100,
+                    initial_block_download: false,
+                }),
+                ..Self::default()
+            }
+        }
+
+        fn rejecting() -> Self {
+            Self {
+                error: FakeError::Rejected,
+                ..Self::offline()
+            }
+        }
+
+        fn requests(&self) -> usize {
+            self.requests.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl BitcoinRelay for FakeRelay {
+        type Error = FakeError;
+
+        async fn send_transaction(
+            &self,
+            transaction: &btc::Transaction,
+        ) -> Result<btc::Sha256d, Self::Error> {
+            self.transaction_status(transaction.id())
+                .await
+                .map(|_| transaction.id())
+        }
+
+        async fn transaction_status(
+            &self,
+            _id: btc::Sha256d,
+        ) -> Result<TransactionStatus, Self::Error> {
+            if self.requests.fetch_add(1, Ordering::SeqCst) < self.failures {
+                Err(self.error)
+            } else {
+                Ok(TransactionStatus::Mempool)
+            }
+        }
+
+        async fn health_check(&self) -> Result<Option<BitcoinNodeInfo>, Self::Error> {
+            if self.failures > self.requests() {
+                Err(self.error)
+            } else {
+                Ok(self.info.clone())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn failover_relay_switches_to_available_relay() {
+        let relay = FailoverRelay::new(vec![
+            FakeRelay::offline(),
+            FakeRelay::default(),
+            FakeRelay::default(),
+        ]);
+        let id = btc::Sha256d::new([1; 32]);
+
+        assert_eq!(
+            relay.transaction_status(id).await,
+            Ok(TransactionStatus::Mempool)
+        );
+        assert_eq!(
+            relay.transaction_status(id).await,
+            Ok(TransactionStatus::Mempool)
+        );
+        // The second relay is used while it is available.
+        assert_eq!(relay.relays[0].requests(), 1);
+        assert_eq!(relay.relays[1].requests(), 2);
+        assert_eq!(relay.relays[2].requests(), 0);
+    }
+
+    #[tokio::test]
+    async fn failover_relay_all_relays_offline() {
+        let relay = FailoverRelay::new(vec![FakeRelay::offline(), FakeRelay::offline()]);
+        let id = btc::Sha256d::new([1; 32]);
+
+        assert_eq!(relay.transaction_status(id).await, Err(FakeError::Offline));
+        assert_eq!(relay.relays[0].requests(), 1);
+        assert_eq!(relay.relays[1].requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn failover_relay_permanent_error() {
+        let relay = FailoverRelay::new(vec![FakeRelay::rejecting(), FakeRelay::default()]);
+        let id = btc::Sha256d::new([1; 32]);
+
+        assert_eq!(relay.transaction_status(id).await, Err(FakeError::Rejected));
+        // The error is not related to the communication, so the next relay is not used.
+        assert_eq!(relay.relays[0].requests(), 1);
+        assert_eq!(relay.relays[1].requests(), 0);
+    }
+
+    #[tokio::test]
+    async fn failover_relay_skips_unhealthy_relays() {
+        let relay = FailoverRelay::new(vec![
+            // The node is in the initial block download state.
+            FakeRelay::with_node_info(50, true),
+            // The node is lagging behind.
+            FakeRelay::with_node_info(90, false),
+            FakeRelay::with_node_info(100, false),
+        ]);
+        let id = btc::Sha256d::new([1; 32]);
+
+        let info = relay.health_check().await.unwrap().unwrap();
+        assert_eq!(info.blocks, 100);
+        // The health check does not switch the relays by itself.
+        assert_eq!(relay.current.load(Ordering::SeqCst), 0);
+
+        assert_eq!(
+            relay.transaction_status(id).await,
+            Ok(TransactionStatus::Mempool)
+        );
+        assert_eq!(relay.relays[0].requests(), 0);
+        assert_eq!(relay.relays[1].requests(), 0);
+        assert_eq!(relay.relays[2].requests(), 1);
+        assert_eq!(relay.current.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn failover_relay_all_relays_unhealthy() {
+        let relay = FailoverRelay::new(vec![
+            FakeRelay::offline(),
+            FakeRelay::with_node_info(50, true),
+        ]);
+        let id = btc::Sha256d::new([1; 32]);
+
+        // The state of the reachable node is reported.
+        let info = relay.health_check().await.unwrap().unwrap();
+        assert!(info.initial_block_download);
+        // Unhealthy relays are still used as the last resort.
+        assert_eq!(
+            relay.transaction_status(id).await,
+            Ok(TransactionStatus::Mempool)
+        );
+        assert_eq!(relay.relays[0].requests(), 1);
+        assert_eq!(relay.relays[1].requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn failover_relay_reports_lowest_version() {
+        let relay = FailoverRelay::new(vec![
+            FakeRelay::with_version(190_000),
+            FakeRelay::with_version(160_000),
+            FakeRelay::offline(),
+        ]);
+
+        // The outdated fallback node is reported even though it is not the current one.
+        let info = relay.health_check().await.unwrap().unwrap();
+        assert_eq!(info.version, Some(160_000));
+        assert_eq!(relay.current.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn retry_relay_recovers_after_failures() {
+        let relay = RetryRelay::new(FakeRelay::with_failures(2))
+            .with_max_attempts(3)
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(2));
+        let id = btc::Sha256d::new([1; 32]);
+
+        assert_eq!(
+            relay.transaction_status(id).await,
+            Ok(TransactionStatus::Mempool)
+        );
+        assert_eq!(relay.inner.requests(), 3);
+    }
+
+    #[tokio::test]
+    async fn retry_relay_max_attempts_exceeded() {
+        let relay = RetryRelay::new(FakeRelay::with_failures(3))
+            .with_max_attempts(3)
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(2));
+        let id = btc::Sha256d::new([1; 32]);
+
+        assert_eq!(relay.transaction_status(id).await, Err(FakeError::Offline));
+        assert_eq!(relay.inner.requests(), 3);
+    }
+
+    #[tokio::test]
+    async fn retry_relay_permanent_error() {
+        let relay = RetryRelay::new(FakeRelay::rejecting())
+            .with_max_attempts(3)
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(2));
+        let id = btc::Sha256d::new([1; 32]);
+
+        assert_eq!(relay.transaction_status(id).await, Err(FakeError::Rejected));
+        assert_eq!(relay.inner.requests(), 1);
+    }
+
+    fn rpc_error(code: i32) -> bitcoincore_rpc::Error {
+        bitcoincore_rpc::Error::JsonRpc(JsonRpcError::Rpc(RpcError {
+            code,
+            message: String::default(),
+            data: None,
+        }))
+    }
+
+    #[test]
+    fn bitcoin_rpc_error_codes() {
+        assert_eq!(
+            rpc_error_code(&rpc_error(-5)),
+            Some(RPC_INVALID_ADDRESS_OR_KEY)
+        );
+        assert_eq!(
+            rpc_error_code(&bitcoincore_rpc::Error::InvalidCookieFile),
+            None
+        );
+
+        assert!(rpc_error(RPC_IN_WARMUP).is_transient());
+        assert!(!rpc_error(RPC_INVALID_ADDRESS_OR_KEY).is_transient());
+        assert!(!rpc_error(RPC_IN_WARMUP).is_transport());
+    }
+
+    #[test]
+    fn retry_relay_delay() {
+        let relay = RetryRelay::new(FakeRelay::default())
+            .with_backoff(Duration::from_secs(1), Duration::from_secs(5))
+            .with_jitter(0.0);
+
+        assert_eq!(relay.delay(0), Duration::from_secs(1));
+        assert_eq!(relay.delay(1), Duration::from_secs(2));
+        assert_eq!(relay.delay(2), Duration::from_secs(4));
+        assert_eq!(relay.delay(3), Duration::from_secs(5));
+        assert_eq!(relay.delay(100), Duration::from_secs(5));
+
+        let relay = relay.with_jitter(0.5);
+        for attempt in 0..10 {
+            let delay = relay.delay(attempt);
+            assert!(delay <= Duration::from_secs(5));
+            assert!(delay >= Duration::from_millis(500));
+        }
+    }
 }
@@ -0,0 +1,292 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bitcoin relay implementation on top of the Electrum protocol.
+
+use async_trait::async_trait;
+use bitcoin_hashes::{sha256, Hash as BitcoinHash};
+use serde::de::{self, DeserializeOwned};
+use serde_derive::Deserialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    time::timeout,
+};
+
+use std::time::Duration;
+
+use super::{BitcoinNodeInfo, BitcoinRelay, RelayError, TransactionStatus};
+use crate::btc;
+
+/// Electrum relay errors.
+#[derive(Debug, Error)]
+pub enum ElectrumError {
+    /// Unable to communicate with the Electrum server.
+    #[error("Electrum server communication failed: {0}")]
+    Io(#[from] std::io::Error),
+    /// Electrum server has sent a malformed response.
+    #[error("Malformed Electrum server response: {0}")]
+    MalformedResponse(#[from] serde_json::Error),
+    /// Electrum server has returned an error.
+    #[error("Electrum server returned an error: {0}")]
+    Server(Value),
+    /// Electrum server has not responded in time.
+    #[error("Electrum server has not responded within {0:?}")]
+    Timeout(Duration),
+}
+
+/// Default timeout of the single request to the Electrum server.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Error code used by the Bitcoin daemon for the unknown transactions.
+const RPC_INVALID_ADDRESS_OR_KEY: i64 = -5;
+/// Error code used by the ElectrumX server for the errors forwarded from the Bitcoin daemon.
+const DAEMON_ERROR: i64 = 2;
+
+impl ElectrumError {
+    /// Returns `true` if the server has reported that the requested transaction is unknown.
+    fn is_not_found(&self) -> bool {
+        let error = match self {
+            ElectrumError::Server(error) => error,
+            _ => return false,
+        };
+        match error.get("code").and_then(Value::as_i64) {
+            Some(RPC_INVALID_ADDRESS_OR_KEY) => true,
+            // ElectrumX wraps the daemon error into the message, including its code.
+            Some(DAEMON_ERROR) => error
+                .get("message")
+                .and_then(Value::as_str)
+                .map_or(false, |message| {
+                    message.contains("No such mempool or blockchain transaction")
+                }),
+            _ => false,
+        }
+    }
+}
+
+impl RelayError for ElectrumError {
+    fn is_transient(&self) -> bool {
+        match self {
+            ElectrumError::Io(_) | ElectrumError::Timeout(_) => true,
+            ElectrumError::MalformedResponse(_) | ElectrumError::Server(_) => false,
+        }
+    }
+
+    fn is_transport(&self) -> bool {
+        self.is_transient()
+    }
+}
+
+/// Bitcoin relay that communicates with the Electrum or ElectrumX server
+/// over plain TCP.
+///
+/// Unlike the Bitcoin node RPC, this relay does not require a full node with
+/// the transaction index. The number of confirmations is obtained from the
+/// history of the first transaction output script.
+///
+/// TLS connections are not supported, so the public servers should be reached
+/// through a TLS tunnel like `stunnel`, or a trusted server should be used.
+#[derive(Debug, Clone)]
+pub struct ElectrumRelay {
+    address: String,
+    timeout: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    #[serde(default)]
+    result: Value,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryItem {
+    tx_hash: btc::Sha256d,
+    height: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct HeaderNotification {
+    height: u64,
+}
+
+impl ElectrumRelay {
+    /// Creates a new Electrum relay for the server with the given address
+    /// in form `{host}:{port}`.
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets the timeout of the single request to the Electrum server, including
+    /// the connection establishment. By default it is 30 seconds.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Performs a single request to the Electrum server. Connections are not
+    /// kept between requests, so the relay never uses a stale connection.
+    async fn request<R>(&self, method: &str, params: Value) -> Result<R, ElectrumError>
+    where
+        R: DeserializeOwned,
+    {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": method,
+            "params": params,
+        });
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+
+        let exchange = async {
+            let mut stream = TcpStream::connect(self.address.as_str()).await?;
+            stream.write_all(line.as_bytes()).await?;
+
+            let mut response = String::new();
+            BufReader::new(stream).read_line(&mut response).await?;
+            Ok::<_, std::io::Error>(response)
+        };
+        let response = timeout(self.timeout, exchange)
+            .await
+            .map_err(|_| ElectrumError::Timeout(self.timeout))??;
+        let response: Response = serde_json::from_str(&response)?;
+        match response.error {
+            Some(error) if !error.is_null() => Err(ElectrumError::Server(error)),
+            _ => serde_json::from_value(response.result).map_err(From::from),
+        }
+    }
+
+    /// Returns the Electrum script hash of the given script, that is reversed
+    /// SHA-256 of the script bytes.
+    fn script_hash(script: &bitcoin::Script) -> String {
+        let mut hash = sha256::Hash::hash(script.as_bytes()).into_inner();
+        hash.reverse();
+        hex::encode(hash)
+    }
+}
+
+#[async_trait]
+impl BitcoinRelay for ElectrumRelay {
+    type Error = ElectrumError;
+
+    async fn send_transaction(
+        &self,
+        transaction: &btc::Transaction,
+    ) -> Result<btc::Sha256d, Self::Error> {
+        self.request(
+            "blockchain.transaction.broadcast",
+            json!([transaction.to_string()]),
+        )
+        .await
+    }
+
+    async fn transaction_status(&self, id: btc::Sha256d) -> Result<TransactionStatus, Self::Error> {
+        let transaction: btc::Transaction = match self
+            .request("blockchain.transaction.get", json!([id]))
+            .await
+        {
+            Ok(transaction) => transaction,
+            // Electrum servers report unknown transactions as a daemon error.
+            Err(e) if e.is_not_found() => return Ok(TransactionStatus::Unknown),
+            Err(e) => return Err(e),
+        };
+
+        let script_hash = transaction
+            .0
+            .output
+            .first()
+            .map(|out| Self::script_hash(&out.script_pubkey))
+            .ok_or_else(|| {
+                ElectrumError::MalformedResponse(de::Error::custom("transaction without outputs"))
+            })?;
+        let history: Vec<HistoryItem> = self
+            .request("blockchain.scripthash.get_history", json!([script_hash]))
+            .await?;
+
+        // Zero and negative heights denote transactions in the memory pool.
+        let height = match history.into_iter().find(|item| item.tx_hash == id) {
+            Some(item) if item.height > 0 => item.height as u64,
+            _ => return Ok(TransactionStatus::Mempool),
+        };
+        let tip: HeaderNotification = self
+            .request("blockchain.headers.subscribe", json!([]))
+            .await?;
+        let confirmations = tip.height.saturating_sub(height) + 1;
+        Ok(TransactionStatus::Committed(confirmations as u32))
+    }
+
+    async fn health_check(&self) -> Result<Option<BitcoinNodeInfo>, Self::Error> {
+        // Electrum servers only serve the synchronized blockchain.
+        let tip: HeaderNotification = self
+            .request("blockchain.headers.subscribe", json!([]))
+            .await?;
+        Ok(Some(BitcoinNodeInfo {
+            version: None,
+            blocks: tip.height,
+            initial_block_download: false,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{ElectrumError, ElectrumRelay};
+
+    #[test]
+    fn electrum_not_found_error() {
+        let not_found = ElectrumError::Server(json!({
+            "code": 2,
+            "message": "daemon error: DaemonError({'code': -5, 'message': \
+                        'No such mempool or blockchain transaction. \
+                        Use gettransaction for wallet transactions.'})"
+        }));
+        assert!(not_found.is_not_found());
+        let not_found = ElectrumError::Server(json!({
+            "code": -5,
+            "message": "No such mempool or blockchain transaction"
+        }));
+        assert!(not_found.is_not_found());
+
+        let warmup = ElectrumError::Server(json!({
+            "code": 2,
+            "message": "daemon error: DaemonError({'code': -28, 'message': 'Loading block index...'})"
+        }));
+        assert!(!warmup.is_not_found());
+        let bad_request = ElectrumError::Server(json!({
+            "code": 1,
+            "message": "invalid tx hash"
+        }));
+        assert!(!bad_request.is_not_found());
+    }
+
+    #[test]
+    fn electrum_script_hash() {
+        // Test vector from the Electrum protocol documentation.
+        let script = bitcoin::Script::from(
+            hex::decode("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").unwrap(),
+        );
+        assert_eq!(
+            ElectrumRelay::script_hash(&script),
+            "8b01df4e368ea28f8dc0423bcf7a4923e3a12d307c875e47a0cfbf90b5c39161"
+        );
+    }
+}
@@ -14,27 +14,38 @@
 
 //! Building blocks of the anchoring sync utility.
 
-pub use self::bitcoin_relay::{BitcoinRelay, TransactionStatus};
+pub use self::{
+    bitcoin_relay::{
+        BitcoinNodeInfo, BitcoinRelay, FailoverRelay, RelayError, RetryRelay, TransactionStatus,
+    },
+    electrum::{ElectrumError, ElectrumRelay},
+    signer::{KeyPool, TransactionSigner},
+};
 
 use anyhow::anyhow;
 use btc_transaction_utils::{p2wsh, TxInRef};
 
-use std::{collections::HashMap, fmt::Display, sync::Arc};
+use std::{
+    cmp,
+    fmt::Display,
+    num::NonZeroU64,
+    sync::{Arc, Mutex},
+};
 
 use crate::{
-    api::{AnchoringProposalState, PrivateApi},
+    api::{AnchoringProposalState, AnchoringTransactionSummary, PrivateApi},
     blockchain::SignInput,
     btc,
     config::Config,
 };
 
 mod bitcoin_relay;
+mod electrum;
+mod signer;
 
 /// Anchoring transaction with its index in the anchoring chain.
 pub type TransactionWithIndex = (btc::Transaction, u64);
 
-type KeyPool = Arc<HashMap<btc::PublicKey, btc::PrivateKey>>;
-
 /// Errors that occur when updating the anchoring chain.
 #[derive(Debug)]
 pub enum ChainUpdateError<C: Display> {
@@ -47,21 +58,46 @@ pub enum ChainUpdateError<C: Display> {
         /// Available balance.
         balance: u64,
     },
+    /// Anchoring output value after paying the fee would be below the dust limit.
+    DustOutput {
+        /// Total transaction fee.
+        total_fee: u64,
+        /// Available balance.
+        balance: u64,
+    },
     /// Initial funding transaction is absent.
     NoInitialFunds,
     /// Internal error.
     Internal(anyhow::Error),
 }
 
+/// Counters of the anchoring proposal inputs processed by the chain update task.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChainUpdateStats {
+    /// Number of inputs signed and sent to the anchoring node.
+    pub signed_inputs: u64,
+    /// Number of inputs skipped, since they had already been signed by the anchoring node
+    /// when the proposal was handled for the first time.
+    pub skipped_inputs: u64,
+    /// Number of inputs which have not been signed or sent because of an error.
+    pub failed_inputs: u64,
+}
+
 /// Signs the inputs of the anchoring transaction proposal by the corresponding
 /// Bitcoin private keys.
 #[derive(Debug)]
-pub struct AnchoringChainUpdateTask<T>
+pub struct AnchoringChainUpdateTask<T, S = KeyPool>
 where
     T: PrivateApi + 'static,
+    S: TransactionSigner + 'static,
 {
-    key_pool: KeyPool,
+    signer: Arc<S>,
     api_client: T,
+    low_funds_threshold: Option<u64>,
+    stats: Mutex<ChainUpdateStats>,
+    /// Identifier of the last handled proposal, used to count the skipped inputs
+    /// only once per proposal.
+    last_proposal_id: Mutex<Option<btc::Sha256d>>,
 }
 
 impl<T> AnchoringChainUpdateTask<T>
@@ -69,17 +105,48 @@ where
     T: PrivateApi + 'static,
     T::Error: Display,
 {
-    /// Creates a new anchoring chain updater instance.
+    /// Creates a new anchoring chain updater instance, which signs the inputs
+    /// by the given Bitcoin keys.
     pub fn new(
         keys: impl IntoIterator<Item = (btc::PublicKey, btc::PrivateKey)>,
         api_client: T,
     ) -> Self {
+        Self::with_signer(keys.into_iter().collect(), api_client)
+    }
+}
+
+impl<T, S> AnchoringChainUpdateTask<T, S>
+where
+    T: PrivateApi + 'static,
+    T::Error: Display,
+    S: TransactionSigner + 'static,
+    S::Error: Display,
+{
+    /// Creates a new anchoring chain updater instance, which signs the inputs
+    /// by the given external signer.
+    pub fn with_signer(signer: S, api_client: T) -> Self {
         Self {
-            key_pool: Arc::new(keys.into_iter().collect()),
+            signer: Arc::new(signer),
             api_client,
+            low_funds_threshold: None,
+            stats: Mutex::default(),
+            last_proposal_id: Mutex::default(),
         }
     }
 
+    /// Sets the balance threshold in satoshis. If the balance of the anchoring wallet
+    /// after the next anchoring transaction is below this threshold, the task warns
+    /// that the anchoring wallet should be replenished.
+    pub fn with_low_funds_threshold(mut self, satoshis: u64) -> Self {
+        self.low_funds_threshold = Some(satoshis);
+        self
+    }
+
+    /// Returns the counters of the inputs processed by this task since its creation.
+    pub fn stats(&self) -> ChainUpdateStats {
+        *self.stats.lock().unwrap()
+    }
+
     /// Returns an actual anchoring configuration.
     pub async fn anchoring_config(&self) -> Result<Config, T::Error> {
         self.api_client.config().await
@@ -104,11 +171,15 @@ where
                     .anchoring_config()
                     .await
                     .map_err(ChainUpdateError::Client)?;
+                self.check_low_funds(&config, &transaction);
                 self.handle_proposal(config, transaction, inputs).await
             }
             AnchoringProposalState::InsufficientFunds { balance, total_fee } => {
                 Err(ChainUpdateError::InsufficientFunds { balance, total_fee })
             }
+            AnchoringProposalState::DustOutput { balance, total_fee } => {
+                Err(ChainUpdateError::DustOutput { balance, total_fee })
+            }
             AnchoringProposalState::NoInitialFunds => Err(ChainUpdateError::NoInitialFunds),
         }
     }
@@ -122,10 +193,13 @@ where
         log::trace!("Got an anchoring proposal: {:?}", proposal);
         // Find among the keys one from which we have a private part.
         // TODO What we have to do if we find more than one key? [ECR-3222]
-        let keypair = if let Some(keypair) =
-            self.find_private_key(config.anchoring_keys.iter().map(|x| x.bitcoin_key))
+        let public_key = if let Some(public_key) = config
+            .anchoring_keys
+            .iter()
+            .map(|x| x.bitcoin_key)
+            .find(|public_key| self.signer.has_key(public_key))
         {
-            keypair
+            public_key
         } else {
             return Ok(());
         };
@@ -141,50 +215,94 @@ where
             }
         };
 
+        // Skip inputs that have already been signed by this node, otherwise the
+        // repeated `SignInput` transactions would fail with `InputAlreadySigned`.
+        let signed_inputs = self
+            .api_client
+            .signed_inputs(proposal.id(), public_key)
+            .await
+            .map_err(ChainUpdateError::Client)?;
+        // Inputs signed by this task are already counted, so only the inputs signed
+        // before the proposal is seen for the first time are counted as skipped.
+        let is_new_proposal =
+            self.last_proposal_id.lock().unwrap().replace(proposal.id()) != Some(proposal.id());
+        if is_new_proposal {
+            self.stats.lock().unwrap().skipped_inputs += signed_inputs.len() as u64;
+        }
+        if signed_inputs.len() == inputs.len() {
+            log::trace!(
+                "All inputs of the anchoring proposal for height {} are already signed",
+                block_height
+            );
+            return Ok(());
+        }
+
         log::info!(
             "Found a new unfinished anchoring transaction proposal for height: {}",
             block_height
         );
 
-        let mut signer = p2wsh::InputSigner::new(redeem_script);
-        let sign_input_messages = inputs
+        let mut input_signer = p2wsh::InputSigner::new(redeem_script);
+        let sighashes = inputs
             .iter()
             .enumerate()
             .map(|(index, proposal_input)| {
-                let signature = signer.sign_input(
+                let sighash = input_signer.signature_hash(
                     TxInRef::new(proposal.as_ref(), index),
                     proposal_input.as_ref(),
-                    &(keypair.1).0.key,
-                )?;
-
-                Ok(SignInput {
-                    input: index as u32,
-                    input_signature: signature.into(),
-                    txid: proposal.id(),
-                })
+                );
+                btc::Sha256d(sighash.into())
             })
-            .collect::<anyhow::Result<Vec<_>>>()
-            .map_err(ChainUpdateError::Internal)?;
+            .collect::<Vec<_>>();
+
+        let mut sign_input_messages = Vec::with_capacity(sighashes.len());
+        for (index, sighash) in sighashes.into_iter().enumerate() {
+            if signed_inputs.contains(&(index as u32)) {
+                continue;
+            }
+            let input_signature =
+                self.signer
+                    .sign_input(public_key, sighash)
+                    .await
+                    .map_err(|e| {
+                        self.stats.lock().unwrap().failed_inputs += 1;
+                        ChainUpdateError::Internal(anyhow!("Unable to sign input {}: {}", index, e))
+                    })?;
+            sign_input_messages.push(SignInput {
+                input: index as u32,
+                input_signature,
+                txid: proposal.id(),
+            });
+        }
         // Send sign input transactions to the Exonum node.
         for sign_input in sign_input_messages {
-            self.api_client
-                .sign_input(sign_input)
-                .await
-                .map_err(ChainUpdateError::Client)?;
+            self.api_client.sign_input(sign_input).await.map_err(|e| {
+                self.stats.lock().unwrap().failed_inputs += 1;
+                ChainUpdateError::Client(e)
+            })?;
+            self.stats.lock().unwrap().signed_inputs += 1;
         }
         Ok(())
     }
 
-    fn find_private_key(
-        &self,
-        anchoring_keys: impl IntoIterator<Item = btc::PublicKey>,
-    ) -> Option<(btc::PublicKey, btc::PrivateKey)> {
-        anchoring_keys.into_iter().find_map(|public_key| {
-            self.key_pool
-                .get(&public_key)
-                .cloned()
-                .map(|private_key| (public_key, private_key))
-        })
+    fn check_low_funds(&self, config: &Config, proposal: &btc::Transaction) {
+        let threshold = if let Some(threshold) = self.low_funds_threshold {
+            threshold
+        } else {
+            return;
+        };
+
+        let balance = proposal.unspent_value().unwrap_or_default();
+        if balance < threshold {
+            log::warn!(
+                "Low funds in the anchoring wallet: the balance after the next anchoring \
+                 transaction is {} satoshis, which is below the threshold of {} satoshis. \
+                 You should send some Bitcoins to the address {}",
+                balance,
+                threshold,
+                config.anchoring_address()
+            );
+        }
     }
 }
 
@@ -199,6 +317,12 @@ pub enum SyncWithBitcoinError<C: Display, R: Display> {
     Internal(anyhow::Error),
     /// Initial funding transaction is unconfirmed.
     UnconfirmedFundingTransaction(btc::Sha256d),
+    /// The first uncommitted anchoring transaction was not found among the specified
+    /// number of the latest anchoring transactions.
+    ScanDepthExceeded(u64),
+    /// The anchoring transaction with the given identifier violates the standard
+    /// relay policy of the Bitcoin nodes and will be rejected by them.
+    PolicyViolation(btc::Sha256d, btc::PolicyViolation),
 }
 
 /// Pushes anchoring transactions to the Bitcoin blockchain.
@@ -210,6 +334,7 @@ where
 {
     btc_relay: R,
     api_client: T,
+    max_scan_depth: Option<NonZeroU64>,
 }
 
 impl<T, R> SyncWithBitcoinTask<T, R>
@@ -224,9 +349,23 @@ where
         Self {
             api_client,
             btc_relay,
+            max_scan_depth: None,
         }
     }
 
+    /// Limits the number of the latest anchoring transactions that will be checked
+    /// while searching for the first uncommitted one. By default, the whole anchoring
+    /// chain may be checked.
+    pub fn with_max_scan_depth(mut self, max_scan_depth: NonZeroU64) -> Self {
+        self.max_scan_depth = Some(max_scan_depth);
+        self
+    }
+
+    /// Returns the Bitcoin relay used by this task.
+    pub fn bitcoin_relay(&self) -> &R {
+        &self.btc_relay
+    }
+
     /// Performs one attempt to send the first uncommitted anchoring transaction into the Bitcoin network, if any.
     /// sign an anchoring proposal, if any. Returns an index of the last committed transaction.
     pub async fn process(
@@ -265,6 +404,9 @@ where
             return Ok(None);
         };
 
+        transaction
+            .check_policy()
+            .map_err(|e| SyncWithBitcoinError::PolicyViolation(transaction.id(), e))?;
         // Send an actual uncommitted transaction into the Bitcoin network.
         self.btc_relay
             .send_transaction(&transaction)
@@ -303,8 +445,13 @@ where
         if status.is_known() {
             return Ok(None);
         }
+        // Lowest index of the transaction that can be checked in according with
+        // the scan depth limit.
+        let lowest_index = self
+            .max_scan_depth
+            .map_or(0, |depth| (last_index + 1).saturating_sub(depth.get()));
         // Try to find the first of uncommitted transactions.
-        for index in (1..=last_index).rev() {
+        for index in (cmp::max(lowest_index, 1)..=last_index).rev() {
             let transaction = self.get_transaction(index).await?;
             log::trace!(
                 "Checking for transaction with index {} and id {}",
@@ -325,6 +472,12 @@ where
             }
         }
 
+        if lowest_index > 0 {
+            return Err(SyncWithBitcoinError::ScanDepthExceeded(
+                last_index + 1 - lowest_index,
+            ));
+        }
+
         // If we reach this branch then the transaction previous to the first one was not
         // committed, but previous transaction for the first anchoring transaction always
         // is funding. This is special case and should be handled in specific way in order
@@ -373,3 +526,21 @@ where
             .map_err(SyncWithBitcoinError::Relay)
     }
 }
+
+/// Fills the numbers of Bitcoin confirmations in the summaries of the anchoring
+/// transactions, as returned by the `transactions-summary` endpoint, using
+/// the given Bitcoin relay. Transactions which are not committed to the Bitcoin
+/// blockchain are left without confirmations.
+pub async fn fill_confirmations<R>(
+    btc_relay: &R,
+    summaries: &mut [AnchoringTransactionSummary],
+) -> Result<(), R::Error>
+where
+    R: BitcoinRelay,
+{
+    for summary in summaries {
+        let status = btc_relay.transaction_status(summary.txid).await?;
+        summary.confirmations = status.confirmations();
+    }
+    Ok(())
+}
@@ -0,0 +1,86 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signing of the anchoring transaction inputs.
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+
+use std::{collections::HashMap, iter::FromIterator};
+
+use crate::btc;
+
+/// Pool of the Bitcoin keys, which is used by the default in-process signer.
+#[derive(Debug, Clone)]
+pub struct KeyPool {
+    keys: HashMap<btc::PublicKey, btc::PrivateKey>,
+    context: secp256k1::Secp256k1<secp256k1::SignOnly>,
+}
+
+impl FromIterator<(btc::PublicKey, btc::PrivateKey)> for KeyPool {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (btc::PublicKey, btc::PrivateKey)>,
+    {
+        Self {
+            keys: iter.into_iter().collect(),
+            context: secp256k1::Secp256k1::signing_only(),
+        }
+    }
+}
+
+/// Signs the inputs of the anchoring transactions.
+///
+/// Implement this trait to keep the Bitcoin private keys outside of the sync utility,
+/// for example, in a hardware security module or in a remote signing daemon.
+#[async_trait]
+pub trait TransactionSigner {
+    /// Error type for the current signer implementation.
+    type Error;
+    /// Returns `true` if the signer has the private key corresponding to
+    /// the given public key.
+    fn has_key(&self, public_key: &btc::PublicKey) -> bool;
+    /// Signs the signature hash of the anchoring transaction input by the private key
+    /// corresponding to the given public key. The signature should use
+    /// the `SIGHASH_ALL` type.
+    async fn sign_input(
+        &self,
+        public_key: btc::PublicKey,
+        sighash: btc::Sha256d,
+    ) -> Result<btc::InputSignature, Self::Error>;
+}
+
+#[async_trait]
+impl TransactionSigner for KeyPool {
+    type Error = anyhow::Error;
+
+    fn has_key(&self, public_key: &btc::PublicKey) -> bool {
+        self.keys.contains_key(public_key)
+    }
+
+    async fn sign_input(
+        &self,
+        public_key: btc::PublicKey,
+        sighash: btc::Sha256d,
+    ) -> Result<btc::InputSignature, Self::Error> {
+        let private_key = self
+            .keys
+            .get(&public_key)
+            .ok_or_else(|| anyhow!("No private key for the public key {}", public_key))?;
+
+        let message = secp256k1::Message::from_slice(&sighash.0[..])?;
+        let signature = self.context.sign(&message, &private_key.0.key);
+        Ok(btc_transaction_utils::InputSignature::new(signature, bitcoin::SigHashType::All).into())
+    }
+}
@@ -35,8 +35,11 @@ use std::collections::BTreeMap;
 
 use crate::{
     api::{
-        AnchoringChainLength, AnchoringProposalState, FindTransactionQuery, IndexQuery, PrivateApi,
-        PublicApi, TransactionProof,
+        AnchoringBalance, AnchoringChainLength, AnchoringProposalState, AnchoringSpendingStats,
+        AnchoringStatus, AnchoringTransactionInfo, AnchoringTransactionSummary, DeriveAddressQuery,
+        DerivedAddress, FindTransactionQuery, IndexQuery, PrivateApi, ProposalSighashes, PublicApi,
+        SignedInputsQuery, TransactionProof, TransactionsPageQuery, TransactionsRangeProof,
+        TransactionsRangeQuery, TxIdQuery,
     },
     blockchain::{AddFunds, BtcAnchoringInterface, Schema, SignInput},
     btc,
@@ -175,6 +178,20 @@ impl AnchoringTestKit {
         }
     }
 
+    /// Creates an anchoring testkit instance for the specified number of anchoring nodes,
+    /// and interval between anchors. The anchoring wallet is funded with the given
+    /// amount of satoshis.
+    ///
+    /// To add funds, this instance commit a block with transactions, so in addition to the
+    /// genesis block this instance contains one more.
+    pub fn new_funded(nodes_num: u16, anchoring_interval: u64, satoshis: u64) -> Self {
+        let mut testkit = Self::new(nodes_num, anchoring_interval);
+        testkit
+            .inner
+            .create_block_with_transactions(testkit.create_funding_confirmation_txs(satoshis).0);
+        testkit
+    }
+
     /// Returns the actual anchoring configuration.
     pub fn actual_anchoring_config(&self) -> Config {
         get_anchoring_schema(&self.inner.snapshot()).actual_config()
@@ -256,6 +273,30 @@ impl AnchoringTestKit {
         signatures
     }
 
+    /// Creates blocks until the specified height is reached. Each of the created blocks
+    /// contains signatures of all anchoring nodes for the anchoring transaction proposal
+    /// available before this block, thus the anchoring chain keeps up with the blockchain.
+    ///
+    /// If there is no suitable proposal, for example due to insufficient funds,
+    /// the block is created without signatures.
+    pub fn create_blocks_with_anchoring_until(&mut self, height: Height) {
+        while self.inner.height() < height {
+            let snapshot = self.inner.snapshot();
+            let has_proposal = matches!(
+                get_anchoring_schema(&snapshot)
+                    .actual_proposed_anchoring_transaction(snapshot.for_core()),
+                Some(Ok(_))
+            );
+
+            if has_proposal {
+                let signatures = self.create_signature_txs().into_iter().flatten();
+                self.inner.create_block_with_transactions(signatures);
+            } else {
+                self.inner.create_block();
+            }
+        }
+    }
+
     /// Creates the confirmation transactions with a funding transaction to the current address
     /// with a given amount of Satoshi.
     pub fn create_funding_confirmation_txs(
@@ -371,16 +412,10 @@ impl AnchoringTestKit {
 }
 
 impl Default for AnchoringTestKit {
-    /// Creates anchoring testkit instance with the unspent funding transaction.
-    ///
-    /// To add funds, this instance commit a block with transactions, so in addition to the
-    /// genesis block this instance contains one more.
+    /// Creates anchoring testkit instance for four anchoring nodes with the unspent
+    /// funding transaction.
     fn default() -> Self {
-        let mut testkit = Self::new(4, 5);
-        testkit
-            .inner
-            .create_block_with_transactions(testkit.create_funding_confirmation_txs(700_000).0);
-        testkit
+        Self::new_funded(4, 5, 700_000)
     }
 }
 
@@ -412,6 +447,59 @@ impl PublicApi for TestKitApiClient {
             .get("config")
             .await
     }
+
+    async fn transactions_range(&self, from: u64, to: u64) -> api::Result<TransactionsRangeProof> {
+        self.public(ApiKind::Service(ANCHORING_INSTANCE_NAME))
+            .query(&TransactionsRangeQuery { from, to })
+            .get("transactions-range")
+            .await
+    }
+
+    async fn transactions_summary(
+        &self,
+        from: u64,
+        count: u64,
+    ) -> api::Result<Vec<AnchoringTransactionSummary>> {
+        self.public(ApiKind::Service(ANCHORING_INSTANCE_NAME))
+            .query(&TransactionsPageQuery { from, count })
+            .get("transactions-summary")
+            .await
+    }
+
+    async fn transaction_info(
+        &self,
+        txid: btc::Sha256d,
+    ) -> api::Result<Option<AnchoringTransactionInfo>> {
+        self.public(ApiKind::Service(ANCHORING_INSTANCE_NAME))
+            .query(&TxIdQuery { txid })
+            .get("transaction-info")
+            .await
+    }
+
+    async fn balance(&self) -> api::Result<AnchoringBalance> {
+        self.public(ApiKind::Service(ANCHORING_INSTANCE_NAME))
+            .get("balance")
+            .await
+    }
+
+    async fn status(&self) -> api::Result<AnchoringStatus> {
+        self.public(ApiKind::Service(ANCHORING_INSTANCE_NAME))
+            .get("status")
+            .await
+    }
+
+    async fn spending_stats(&self) -> api::Result<AnchoringSpendingStats> {
+        self.public(ApiKind::Service(ANCHORING_INSTANCE_NAME))
+            .get("spending-stats")
+            .await
+    }
+
+    async fn derive_address(&self, query: DeriveAddressQuery) -> api::Result<DerivedAddress> {
+        self.public(ApiKind::Service(ANCHORING_INSTANCE_NAME))
+            .query(&query)
+            .get("address/derive")
+            .await
+    }
 }
 
 #[async_trait]
@@ -438,6 +526,23 @@ impl PrivateApi for TestKitApiClient {
             .await
     }
 
+    async fn anchoring_proposal_sighashes(&self) -> api::Result<Option<ProposalSighashes>> {
+        self.private(ApiKind::Service(ANCHORING_INSTANCE_NAME))
+            .get("anchoring-proposal/sighashes")
+            .await
+    }
+
+    async fn signed_inputs(
+        &self,
+        txid: btc::Sha256d,
+        bitcoin_key: btc::PublicKey,
+    ) -> api::Result<Vec<u32>> {
+        self.private(ApiKind::Service(ANCHORING_INSTANCE_NAME))
+            .query(&SignedInputsQuery { txid, bitcoin_key })
+            .get("anchoring-proposal/signed-inputs")
+            .await
+    }
+
     async fn config(&self) -> api::Result<Config> {
         self.private(ApiKind::Service(ANCHORING_INSTANCE_NAME))
             .get("config")
@@ -482,3 +587,18 @@ impl ValidateProof for TransactionProof {
         Ok(entry)
     }
 }
+
+impl ValidateProof for TransactionsRangeProof {
+    type Output = Vec<(u64, btc::Transaction)>;
+
+    fn validate(self, validator_keys: &[PublicKey]) -> anyhow::Result<Self::Output> {
+        let (_, index_hash) = self.index_proof.verify(validator_keys)?;
+
+        let entries = self
+            .transactions_proof
+            .check_against_hash(index_hash)?
+            .entries()
+            .to_vec();
+        Ok(entries)
+    }
+}
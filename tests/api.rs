@@ -11,10 +11,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use bitcoin::secp256k1;
 use btc_transaction_utils::{p2wsh, TxInRef};
 use exonum::helpers::Height;
 use exonum_btc_anchoring::{
-    api::{AnchoringProposalState, PrivateApi, PublicApi},
+    api::{
+        AnchoringProposalState, DeriveAddressQuery, PrivateApi, PublicApi, MAX_TRANSACTIONS_RANGE,
+    },
     blockchain::SignInput,
     btc,
     test_helpers::{
@@ -307,6 +310,279 @@ async fn find_transaction_configuration_change() {
     );
 }
 
+#[tokio::test]
+async fn transactions_range() {
+    let (mut anchoring_testkit, anchoring_api) = init_testkit();
+    let anchoring_interval = anchoring_testkit
+        .actual_anchoring_config()
+        .anchoring_interval;
+    anchoring_testkit.create_blocks_with_anchoring_until(Height(anchoring_interval * 3 + 1));
+
+    let validator_keys = anchoring_testkit
+        .inner
+        .consensus_config()
+        .validator_keys
+        .into_iter()
+        .map(|key| key.consensus_key)
+        .collect::<Vec<_>>();
+    let snapshot = anchoring_testkit.inner.snapshot();
+    let tx_chain = get_anchoring_schema(&snapshot).transactions_chain;
+    assert_eq!(tx_chain.len(), 4);
+
+    let entries = anchoring_api
+        .client()
+        .transactions_range(1, 3)
+        .await
+        .unwrap()
+        .validate(&validator_keys)
+        .unwrap();
+    assert_eq!(
+        entries,
+        vec![(1, tx_chain.get(1).unwrap()), (2, tx_chain.get(2).unwrap())]
+    );
+
+    // Indices beyond the chain length are ignored.
+    let entries = anchoring_api
+        .client()
+        .transactions_range(2, 10)
+        .await
+        .unwrap()
+        .validate(&validator_keys)
+        .unwrap();
+    assert_eq!(
+        entries,
+        vec![(2, tx_chain.get(2).unwrap()), (3, tx_chain.get(3).unwrap())]
+    );
+
+    // Malformed range.
+    anchoring_api
+        .client()
+        .transactions_range(3, 1)
+        .await
+        .expect_err("Malformed range should be rejected");
+}
+
+#[tokio::test]
+async fn transactions_summary() {
+    let (mut anchoring_testkit, anchoring_api) = init_testkit();
+    let config = anchoring_testkit.actual_anchoring_config();
+    anchoring_testkit.create_blocks_with_anchoring_until(Height(config.anchoring_interval * 3 + 1));
+
+    let snapshot = anchoring_testkit.inner.snapshot();
+    let tx_chain = get_anchoring_schema(&snapshot).transactions_chain;
+    assert_eq!(tx_chain.len(), 4);
+
+    // The first transaction spends the funding transaction, the following ones
+    // spend the previous anchoring transactions.
+    let summaries = anchoring_api
+        .client()
+        .transactions_summary(0, 10)
+        .await
+        .unwrap();
+    assert_eq!(summaries.len(), 4);
+    for (index, summary) in summaries.into_iter().enumerate() {
+        let tx = tx_chain.get(index as u64).unwrap();
+        assert_eq!(summary.index, index as u64);
+        assert_eq!(summary.txid, tx.id());
+        assert_eq!(summary.payload, tx.anchoring_payload().unwrap());
+        assert_eq!(
+            summary.fee,
+            Some(tx.unsigned_size() * config.transaction_fee)
+        );
+    }
+
+    // The page is limited by the given count.
+    let summaries = anchoring_api
+        .client()
+        .transactions_summary(1, 2)
+        .await
+        .unwrap();
+    let indices = summaries
+        .iter()
+        .map(|summary| summary.index)
+        .collect::<Vec<_>>();
+    assert_eq!(indices, vec![1, 2]);
+    // The service does not know the numbers of Bitcoin confirmations.
+    assert!(summaries
+        .iter()
+        .all(|summary| summary.confirmations.is_none()));
+
+    // Too large page.
+    anchoring_api
+        .client()
+        .transactions_summary(0, MAX_TRANSACTIONS_RANGE + 1)
+        .await
+        .expect_err("Too large page should be rejected");
+}
+
+#[tokio::test]
+async fn transaction_info() {
+    let (mut anchoring_testkit, anchoring_api) = init_testkit();
+    let anchoring_interval = anchoring_testkit
+        .actual_anchoring_config()
+        .anchoring_interval;
+    anchoring_testkit.create_blocks_with_anchoring_until(Height(anchoring_interval + 1));
+
+    let snapshot = anchoring_testkit.inner.snapshot();
+    let tx_chain = get_anchoring_schema(&snapshot).transactions_chain;
+    assert_eq!(tx_chain.len(), 2);
+    for (index, transaction) in tx_chain.iter().enumerate() {
+        let info = anchoring_api
+            .client()
+            .transaction_info(transaction.id())
+            .await
+            .unwrap()
+            .expect("Transaction should belong to the anchoring chain");
+        assert_eq!(info.index, index as u64);
+        assert_eq!(info.payload, transaction.anchoring_payload().unwrap());
+    }
+
+    // Funding transaction is not a part of the anchoring chain.
+    let funding_txid = tx_chain.get(0).unwrap().prev_tx_id();
+    assert_eq!(
+        anchoring_api
+            .client()
+            .transaction_info(funding_txid)
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn balance() {
+    let (mut anchoring_testkit, anchoring_api) = init_testkit();
+
+    // Anchoring chain is not established yet, so only the funding transaction is available.
+    let balance = anchoring_api.client().balance().await.unwrap();
+    assert_eq!(balance.balance, 700_000);
+    assert_eq!(balance.fee_per_anchor, None);
+    assert_eq!(balance.anchors_remaining, None);
+
+    anchoring_testkit.inner.create_block_with_transactions(
+        anchoring_testkit
+            .create_signature_txs()
+            .into_iter()
+            .flatten(),
+    );
+
+    let balance = anchoring_api.client().balance().await.unwrap();
+    assert_eq!(balance.balance, 700_000 - 1530);
+    assert_eq!(balance.fee_per_anchor, Some(1530));
+    assert_eq!(balance.anchors_remaining, Some((700_000 - 1530) / 1530));
+}
+
+#[tokio::test]
+async fn status() {
+    let (mut anchoring_testkit, anchoring_api) = init_testkit();
+    let anchoring_interval = anchoring_testkit
+        .actual_anchoring_config()
+        .anchoring_interval;
+
+    // The proposal for the genesis block is waiting for the signatures.
+    let status = anchoring_api.client().status().await.unwrap();
+    assert!(!status.is_transition);
+    assert_eq!(status.latest_anchored_height, None);
+    assert_eq!(status.next_anchoring_height, Height(0));
+    assert_eq!(status.lag, anchoring_testkit.inner.height().0);
+    assert!(status.proposal_pending);
+    assert_eq!(status.balance, 700_000);
+
+    anchoring_testkit.create_blocks_with_anchoring_until(Height(anchoring_interval + 1));
+
+    let status = anchoring_api.client().status().await.unwrap();
+    assert_eq!(
+        status.latest_anchored_height,
+        Some(Height(anchoring_interval))
+    );
+    assert_eq!(status.next_anchoring_height, Height(anchoring_interval * 2));
+    assert_eq!(status.lag, 0);
+    assert!(!status.proposal_pending);
+    assert_eq!(status.balance, 700_000 - 1530 * 2);
+}
+
+#[tokio::test]
+async fn spending_stats() {
+    let (mut anchoring_testkit, anchoring_api) = init_testkit();
+    let anchoring_interval = anchoring_testkit
+        .actual_anchoring_config()
+        .anchoring_interval;
+
+    let stats = anchoring_api.client().spending_stats().await.unwrap();
+    assert_eq!(stats.transactions_count, 0);
+    assert_eq!(stats.total_fee, 0);
+    assert_eq!(stats.average_fee, None);
+    assert_eq!(stats.unknown_fee_count, 0);
+    assert_eq!(stats.balance, 700_000);
+
+    anchoring_testkit.create_blocks_with_anchoring_until(Height(anchoring_interval + 1));
+
+    let stats = anchoring_api.client().spending_stats().await.unwrap();
+    assert_eq!(stats.transactions_count, 2);
+    assert_eq!(stats.total_fee, 1530 * 2);
+    assert_eq!(stats.average_fee, Some(1530));
+    assert_eq!(stats.unknown_fee_count, 0);
+    assert_eq!(stats.balance + stats.total_fee, 700_000);
+}
+
+#[tokio::test]
+async fn derive_address() {
+    let (anchoring_testkit, anchoring_api) = init_testkit();
+
+    let config = anchoring_testkit.actual_anchoring_config();
+    let bitcoin_keys = config
+        .anchoring_keys
+        .iter()
+        .map(|x| x.bitcoin_key)
+        .collect::<Vec<_>>();
+
+    // Address derived with the default quorum should be equal to the actual one.
+    let derived = anchoring_api
+        .client()
+        .derive_address(DeriveAddressQuery {
+            bitcoin_keys: bitcoin_keys.clone(),
+            quorum: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(derived.address, config.anchoring_address());
+    assert_eq!(derived.quorum, config.byzantine_quorum());
+
+    let derived = anchoring_api
+        .client()
+        .derive_address(DeriveAddressQuery {
+            bitcoin_keys: bitcoin_keys.clone(),
+            quorum: Some(bitcoin_keys.len()),
+        })
+        .await
+        .unwrap();
+    assert_ne!(derived.address, config.anchoring_address());
+    assert_eq!(derived.quorum, bitcoin_keys.len());
+
+    // Quorum cannot be greater than the number of keys.
+    anchoring_api
+        .client()
+        .derive_address(DeriveAddressQuery {
+            bitcoin_keys: bitcoin_keys.clone(),
+            quorum: Some(bitcoin_keys.len() + 1),
+        })
+        .await
+        .unwrap_err();
+
+    // Number of keys cannot exceed the multisig limit.
+    let too_many_keys = (0..21)
+        .map(|_| btc::gen_keypair(config.network).0)
+        .collect();
+    anchoring_api
+        .client()
+        .derive_address(DeriveAddressQuery {
+            bitcoin_keys: too_many_keys,
+            quorum: None,
+        })
+        .await
+        .unwrap_err();
+}
+
 #[tokio::test]
 async fn actual_config() {
     let (anchoring_testkit, anchoring_api) = init_testkit();
@@ -379,6 +655,28 @@ async fn anchoring_proposal_err_insufficient_funds() {
     );
 }
 
+#[tokio::test]
+async fn anchoring_proposal_err_dust_output() {
+    let mut anchoring_testkit = AnchoringTestKit::new(4, 5);
+
+    // The change after paying the fee is below the dust limit.
+    anchoring_testkit.inner.create_block_with_transactions(
+        anchoring_testkit
+            .create_funding_confirmation_txs(1530 + 100)
+            .0,
+    );
+
+    let api = anchoring_testkit.inner.api();
+    let state = api.client().anchoring_proposal().await.unwrap();
+    assert_eq!(
+        state,
+        AnchoringProposalState::DustOutput {
+            total_fee: 1530,
+            balance: 1630
+        }
+    );
+}
+
 #[tokio::test]
 async fn sign_input() {
     let (mut anchoring_testkit, anchoring_api) = init_testkit();
@@ -429,6 +727,73 @@ async fn sign_input() {
         .expect("Transaction should be successful");
 }
 
+#[tokio::test]
+async fn anchoring_proposal_sighashes() {
+    let (mut anchoring_testkit, anchoring_api) = init_testkit();
+
+    let config = anchoring_testkit.actual_anchoring_config();
+    let bitcoin_public_key = config
+        .find_bitcoin_key(&anchoring_testkit.inner.us().service_keypair().public_key())
+        .unwrap()
+        .1;
+    let bitcoin_private_key = anchoring_testkit.node_private_key(&bitcoin_public_key);
+
+    let (proposal, _) = anchoring_testkit.anchoring_transaction_proposal().unwrap();
+    let proposal_sighashes = anchoring_api
+        .client()
+        .anchoring_proposal_sighashes()
+        .await
+        .unwrap()
+        .expect("Proposal should be available");
+    assert_eq!(proposal_sighashes.txid, proposal.id());
+    assert_eq!(proposal_sighashes.sighashes.len(), 1);
+
+    // Sign the input the same way as an external custodian does.
+    let message = secp256k1::Message::from_slice(&proposal_sighashes.sighashes[0].0[..]).unwrap();
+    let signature = btc_transaction_utils::InputSignature::new(
+        secp256k1::Secp256k1::signing_only().sign(&message, &bitcoin_private_key.0.key),
+        bitcoin::SigHashType::All,
+    );
+
+    let tx_hash = anchoring_api
+        .client()
+        .sign_input(SignInput {
+            input: 0,
+            input_signature: signature.into(),
+            txid: proposal.id(),
+        })
+        .await
+        .unwrap();
+
+    anchoring_testkit
+        .inner
+        .create_block_with_tx_hashes(&[tx_hash])[0]
+        .status()
+        .expect("Transaction should be successful");
+}
+
+#[tokio::test]
+async fn anchoring_proposal_sighashes_none() {
+    let (mut anchoring_testkit, anchoring_api) = init_testkit();
+
+    // Establish anchoring transactions chain.
+    anchoring_testkit.inner.create_block_with_transactions(
+        anchoring_testkit
+            .create_signature_txs()
+            .into_iter()
+            .flatten(),
+    );
+
+    assert_eq!(
+        anchoring_api
+            .client()
+            .anchoring_proposal_sighashes()
+            .await
+            .unwrap(),
+        None
+    );
+}
+
 #[tokio::test]
 async fn add_funds_ok() {
     let anchoring_interval = 5;
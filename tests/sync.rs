@@ -20,13 +20,13 @@ use exonum::{
     messages::{AnyTx, Verified},
 };
 use exonum_btc_anchoring::{
-    api::{AnchoringChainLength, AnchoringProposalState, PrivateApi},
+    api::{AnchoringChainLength, AnchoringProposalState, PrivateApi, ProposalSighashes, PublicApi},
     blockchain::{AddFunds, BtcAnchoringInterface, SignInput},
     btc,
     config::Config,
     sync::{
-        AnchoringChainUpdateTask, BitcoinRelay, ChainUpdateError, SyncWithBitcoinError,
-        SyncWithBitcoinTask, TransactionStatus,
+        self, AnchoringChainUpdateTask, BitcoinRelay, ChainUpdateError, ChainUpdateStats, KeyPool,
+        SyncWithBitcoinError, SyncWithBitcoinTask, TransactionSigner, TransactionStatus,
     },
     test_helpers::{get_anchoring_schema, AnchoringTestKit, ANCHORING_INSTANCE_ID},
 };
@@ -35,6 +35,7 @@ use exonum_testkit::TestKitApiClient;
 
 use std::{
     collections::VecDeque,
+    num::NonZeroU64,
     sync::{Arc, Mutex},
 };
 
@@ -48,35 +49,40 @@ enum FakeRelayRequest {
         request: btc::Sha256d,
         response: TransactionStatus,
     },
+    /// Matches the `transaction_status` request with any transaction ID.
+    AnyTransactionStatus { response: TransactionStatus },
 }
 
 impl FakeRelayRequest {
-    fn into_send_transaction(self) -> (btc::Transaction, btc::Sha256d) {
-        if let FakeRelayRequest::SendTransaction { request, response } = self {
-            (request, response)
-        } else {
-            panic!(
-                "Expected response for the `send_transaction` request. But got {:?}",
-                self
-            )
+    fn send_transaction_response(&self, transaction: &btc::Transaction) -> Option<btc::Sha256d> {
+        match self {
+            FakeRelayRequest::SendTransaction { request, response } if request == transaction => {
+                Some(*response)
+            }
+            _ => None,
         }
     }
 
-    fn into_transaction_status(self) -> (btc::Sha256d, TransactionStatus) {
-        if let FakeRelayRequest::TransactionStatus { request, response } = self {
-            (request, response)
-        } else {
-            panic!(
-                "Expected response for the `transaction_confirmations` request. But got {:?}",
-                self
-            )
+    fn transaction_status_response(&self, id: btc::Sha256d) -> Option<TransactionStatus> {
+        match self {
+            FakeRelayRequest::TransactionStatus { request, response } if *request == id => {
+                Some(*response)
+            }
+            FakeRelayRequest::AnyTransactionStatus { response } => Some(*response),
+            _ => None,
         }
     }
 }
 
+/// Fake relay with the expected requests.
+///
+/// Ordered requests should be handled in the same order as they were enqueued, while
+/// unordered ones may be handled at any moment. All of the expected requests should be
+/// handled before the relay is dropped.
 #[derive(Debug, Clone, Default)]
 struct FakeBitcoinRelay {
     requests: Arc<Mutex<VecDeque<FakeRelayRequest>>>,
+    unordered_requests: Arc<Mutex<Vec<FakeRelayRequest>>>,
 }
 
 impl FakeBitcoinRelay {
@@ -84,12 +90,38 @@ impl FakeBitcoinRelay {
         self.requests.lock().unwrap().extend(requests)
     }
 
-    fn dequeue_request(&self) -> FakeRelayRequest {
-        self.requests
-            .lock()
-            .unwrap()
-            .pop_front()
-            .expect("Expected relay request")
+    fn expect_unordered_requests(&self, requests: impl IntoIterator<Item = FakeRelayRequest>) {
+        self.unordered_requests.lock().unwrap().extend(requests)
+    }
+
+    fn handle_request<T>(
+        &self,
+        request: impl std::fmt::Debug,
+        response: impl Fn(&FakeRelayRequest) -> Option<T>,
+    ) -> T {
+        // The next ordered request has priority over the unordered ones.
+        let mut requests = self.requests.lock().unwrap();
+        if let Some(value) = requests.front().and_then(&response) {
+            requests.pop_front();
+            return value;
+        }
+
+        let mut unordered_requests = self.unordered_requests.lock().unwrap();
+        if let Some(value) = unordered_requests
+            .iter()
+            .position(|expected| response(expected).is_some())
+            .and_then(|index| response(&unordered_requests.remove(index)))
+        {
+            return value;
+        }
+
+        panic!(
+            "Unexpected relay request {:?}. The next expected request is {:?}, \
+             unordered requests are {:?}",
+            request,
+            requests.front(),
+            unordered_requests
+        )
     }
 }
 
@@ -97,7 +129,8 @@ impl Drop for FakeBitcoinRelay {
     fn drop(&mut self) {
         if !std::thread::panicking() {
             assert!(
-                self.requests.lock().unwrap().is_empty(),
+                self.requests.lock().unwrap().is_empty()
+                    && self.unordered_requests.lock().unwrap().is_empty(),
                 "Unhandled requests remained. {:?}",
                 self
             );
@@ -113,15 +146,13 @@ impl BitcoinRelay for FakeBitcoinRelay {
         &self,
         transaction: &btc::Transaction,
     ) -> Result<btc::Sha256d, Self::Error> {
-        let (expected_request, response) = self.dequeue_request().into_send_transaction();
-        assert_eq!(&expected_request, transaction, "Unexpected data in request");
-        Ok(response)
+        Ok(self.handle_request(transaction, |expected| {
+            expected.send_transaction_response(transaction)
+        }))
     }
 
     async fn transaction_status(&self, id: btc::Sha256d) -> Result<TransactionStatus, Self::Error> {
-        let (expected_request, response) = self.dequeue_request().into_transaction_status();
-        assert_eq!(expected_request, id, "Unexpected data in request");
-        Ok(response)
+        Ok(self.handle_request(id, |expected| expected.transaction_status_response(id)))
     }
 }
 
@@ -188,6 +219,18 @@ impl PrivateApi for FakePrivateApi {
         self.client.anchoring_proposal().await
     }
 
+    async fn anchoring_proposal_sighashes(&self) -> Result<Option<ProposalSighashes>, Self::Error> {
+        self.client.anchoring_proposal_sighashes().await
+    }
+
+    async fn signed_inputs(
+        &self,
+        txid: btc::Sha256d,
+        bitcoin_key: btc::PublicKey,
+    ) -> Result<Vec<u32>, Self::Error> {
+        self.client.signed_inputs(txid, bitcoin_key).await
+    }
+
     async fn config(&self) -> Result<Config, Self::Error> {
         self.client.config().await
     }
@@ -243,6 +286,139 @@ async fn chain_updater_normal() {
     }
 }
 
+/// Signer that keeps keys in memory and records the signed sighashes, like an external
+/// signing daemon does.
+#[derive(Debug)]
+struct FakeSigner {
+    keys: KeyPool,
+    signed: Arc<Mutex<Vec<btc::Sha256d>>>,
+}
+
+#[async_trait]
+impl TransactionSigner for FakeSigner {
+    type Error = anyhow::Error;
+
+    fn has_key(&self, public_key: &btc::PublicKey) -> bool {
+        self.keys.has_key(public_key)
+    }
+
+    async fn sign_input(
+        &self,
+        public_key: btc::PublicKey,
+        sighash: btc::Sha256d,
+    ) -> Result<btc::InputSignature, Self::Error> {
+        self.signed.lock().unwrap().push(sighash);
+        self.keys.sign_input(public_key, sighash).await
+    }
+}
+
+#[tokio::test]
+async fn chain_updater_external_signer() {
+    let mut testkit = AnchoringTestKit::default();
+    let api = testkit.inner.api();
+
+    let sighashes = api
+        .client()
+        .anchoring_proposal_sighashes()
+        .await
+        .unwrap()
+        .expect("Proposal should be available")
+        .sighashes;
+
+    let signed = Arc::new(Mutex::new(Vec::new()));
+    for keypair in testkit.anchoring_keypairs() {
+        let private_api =
+            FakePrivateApi::for_anchoring_node(&testkit, api.client().clone(), &keypair.0);
+        let signer = FakeSigner {
+            keys: std::iter::once(keypair).collect(),
+            signed: signed.clone(),
+        };
+
+        AnchoringChainUpdateTask::with_signer(signer, private_api)
+            .process()
+            .await
+            .unwrap();
+    }
+    testkit.inner.create_block();
+    // Make sure the anchoring proposal has been finalized.
+    assert_eq!(
+        anchoring_transaction_payload(&testkit, 0)
+            .unwrap()
+            .block_height,
+        Height(0)
+    );
+    // Each node has signed every input of the proposal.
+    let signed = signed.lock().unwrap();
+    assert_eq!(
+        signed.len(),
+        testkit.anchoring_keypairs().len() * sighashes.len()
+    );
+    assert!(signed.iter().all(|sighash| sighashes.contains(sighash)));
+}
+
+#[tokio::test]
+async fn chain_updater_skips_signed_inputs() {
+    let mut testkit = AnchoringTestKit::default();
+    let api = testkit.inner.api();
+
+    let keypair = testkit.anchoring_keypairs().into_iter().next().unwrap();
+    let signed = Arc::new(Mutex::new(Vec::new()));
+    let private_api =
+        FakePrivateApi::for_anchoring_node(&testkit, api.client().clone(), &keypair.0);
+    let signer = FakeSigner {
+        keys: std::iter::once(keypair.clone()).collect(),
+        signed: signed.clone(),
+    };
+    let task = AnchoringChainUpdateTask::with_signer(signer, private_api);
+    // Process the same proposal twice by the single anchoring node, so the proposal
+    // stays unfinished.
+    for _ in 0..2 {
+        task.process().await.unwrap();
+        testkit.inner.create_block();
+    }
+    // Inputs signed during the first run should not be signed again.
+    let proposal = api
+        .client()
+        .anchoring_proposal_sighashes()
+        .await
+        .unwrap()
+        .expect("Proposal should be available");
+    assert_eq!(signed.lock().unwrap().len(), proposal.sighashes.len());
+    assert_eq!(
+        api.client()
+            .signed_inputs(proposal.txid, keypair.0)
+            .await
+            .unwrap(),
+        (0..proposal.sighashes.len() as u32).collect::<Vec<_>>()
+    );
+    let inputs_count = proposal.sighashes.len() as u64;
+    assert_eq!(
+        task.stats(),
+        ChainUpdateStats {
+            signed_inputs: inputs_count,
+            skipped_inputs: 0,
+            failed_inputs: 0,
+        }
+    );
+
+    // A restarted task counts the inputs signed before only once per proposal.
+    let private_api =
+        FakePrivateApi::for_anchoring_node(&testkit, api.client().clone(), &keypair.0);
+    let task = AnchoringChainUpdateTask::new(std::iter::once(keypair), private_api);
+    for _ in 0..2 {
+        task.process().await.unwrap();
+        testkit.inner.create_block();
+    }
+    assert_eq!(
+        task.stats(),
+        ChainUpdateStats {
+            signed_inputs: 0,
+            skipped_inputs: inputs_count,
+            failed_inputs: 0,
+        }
+    );
+}
+
 #[tokio::test]
 async fn chain_updater_no_initial_funds() {
     let anchoring_interval = 5;
@@ -374,6 +550,38 @@ async fn sync_with_bitcoin_normal() {
     assert_eq!(latest_committed_tx_index, 1);
 }
 
+#[tokio::test]
+async fn sync_with_bitcoin_next_transaction() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_interval = testkit.actual_anchoring_config().anchoring_interval;
+    testkit.create_blocks_with_anchoring_until(Height(anchoring_interval + 1));
+
+    let snapshot = testkit.inner.snapshot();
+    let anchoring_schema = get_anchoring_schema(&snapshot);
+    let tx_chain = anchoring_schema.transactions_chain;
+    assert_eq!(tx_chain.len(), 2);
+
+    let fake_relay = FakeBitcoinRelay::default();
+    let api = testkit.inner.api();
+    let sync = SyncWithBitcoinTask::new(fake_relay.clone(), api.client().clone());
+    // If the latest committed transaction is known, the next one should be sent.
+    fake_relay.expect_unordered_requests(vec![
+        FakeRelayRequest::SendTransaction {
+            request: tx_chain.get(1).unwrap(),
+            response: tx_chain.get(1).unwrap().id(),
+        },
+        FakeRelayRequest::AnyTransactionStatus {
+            response: TransactionStatus::Committed(1),
+        },
+    ]);
+    let latest_committed_tx_index = sync
+        .process(Some(0))
+        .await
+        .unwrap()
+        .expect("Transaction should be committed");
+    assert_eq!(latest_committed_tx_index, 1);
+}
+
 #[tokio::test]
 async fn sync_with_bitcoin_empty_chain() {
     let mut testkit = AnchoringTestKit::default();
@@ -421,3 +629,78 @@ async fn sync_with_bitcoin_err_unconfirmed_funding_tx() {
         e => panic!("Unexpected error occurred: {:?}", e),
     }
 }
+
+#[tokio::test]
+async fn sync_with_bitcoin_err_scan_depth_exceeded() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_interval = testkit.actual_anchoring_config().anchoring_interval;
+    // Create a several anchoring transactions
+    for i in 0..3 {
+        testkit
+            .inner
+            .create_blocks_until(Height(anchoring_interval * i));
+
+        testkit
+            .inner
+            .create_block_with_transactions(testkit.create_signature_txs().into_iter().flatten());
+    }
+
+    let snapshot = testkit.inner.snapshot();
+    let anchoring_schema = get_anchoring_schema(&snapshot);
+    let tx_chain = anchoring_schema.transactions_chain;
+    assert_eq!(tx_chain.len(), 3);
+
+    let fake_relay = FakeBitcoinRelay::default();
+    let api = testkit.inner.api();
+    let sync = SyncWithBitcoinTask::new(fake_relay.clone(), api.client().clone())
+        .with_max_scan_depth(NonZeroU64::new(2).unwrap());
+    // Only the two latest transactions should be checked.
+    fake_relay.enqueue_requests(vec![
+        FakeRelayRequest::TransactionStatus {
+            request: tx_chain.get(2).unwrap().id(),
+            response: TransactionStatus::Unknown,
+        },
+        FakeRelayRequest::TransactionStatus {
+            request: tx_chain.get(1).unwrap().id(),
+            response: TransactionStatus::Unknown,
+        },
+        FakeRelayRequest::TransactionStatus {
+            request: tx_chain.get(0).unwrap().id(),
+            response: TransactionStatus::Unknown,
+        },
+    ]);
+
+    let e = sync.process(None).await.unwrap_err();
+    match e {
+        SyncWithBitcoinError::ScanDepthExceeded(depth) => assert_eq!(depth, 2),
+        e => panic!("Unexpected error occurred: {:?}", e),
+    }
+}
+
+#[tokio::test]
+async fn fill_transactions_confirmations() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_interval = testkit.actual_anchoring_config().anchoring_interval;
+    testkit.create_blocks_with_anchoring_until(Height(anchoring_interval + 1));
+
+    let api = testkit.inner.api();
+    let mut summaries = api.client().transactions_summary(0, 10).await.unwrap();
+    assert_eq!(summaries.len(), 2);
+
+    let fake_relay = FakeBitcoinRelay::default();
+    fake_relay.enqueue_requests(vec![
+        FakeRelayRequest::TransactionStatus {
+            request: summaries[0].txid,
+            response: TransactionStatus::Committed(3),
+        },
+        FakeRelayRequest::TransactionStatus {
+            request: summaries[1].txid,
+            response: TransactionStatus::Mempool,
+        },
+    ]);
+    sync::fill_confirmations(&fake_relay, &mut summaries)
+        .await
+        .unwrap();
+    assert_eq!(summaries[0].confirmations, Some(3));
+    assert_eq!(summaries[1].confirmations, None);
+}
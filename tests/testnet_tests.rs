@@ -206,6 +206,80 @@ fn simple() {
     assert!(tx1_meta.1.block_height == Height(anchoring_interval));
 }
 
+#[test]
+fn anchoring_until_height() {
+    let mut anchoring_testkit = AnchoringTestKit::default();
+    let anchoring_interval = anchoring_testkit
+        .actual_anchoring_config()
+        .anchoring_interval;
+
+    anchoring_testkit.create_blocks_with_anchoring_until(Height(anchoring_interval * 3 + 1));
+    assert_eq!(
+        anchoring_testkit.inner.height(),
+        Height(anchoring_interval * 3 + 1)
+    );
+
+    // Each of the reached anchoring heights should be anchored.
+    let snapshot = anchoring_testkit.inner.snapshot();
+    let tx_chain = get_anchoring_schema(&snapshot).transactions_chain;
+    assert_eq!(tx_chain.len(), 4);
+    for (index, tx) in tx_chain.iter().enumerate() {
+        let payload = tx.anchoring_payload().unwrap();
+        assert_eq!(
+            payload.block_height,
+            Height(anchoring_interval * index as u64)
+        );
+    }
+    assert!(anchoring_testkit.anchoring_transaction_proposal().is_none());
+}
+
+fn test_anchoring_quorum(nodes_num: u16) {
+    let mut anchoring_testkit = AnchoringTestKit::new_funded(nodes_num, 5, 100_000);
+    let quorum = anchoring_testkit
+        .actual_anchoring_config()
+        .byzantine_quorum();
+    let signatures = anchoring_testkit.create_signature_txs();
+    assert_eq!(signatures.len(), nodes_num as usize);
+
+    // Commit signatures, which are not enough to reach the quorum.
+    anchoring_testkit
+        .inner
+        .create_block_with_transactions(signatures[..quorum - 1].iter().flatten().cloned());
+    assert!(anchoring_testkit.last_anchoring_tx().is_none());
+    // Reach the quorum.
+    anchoring_testkit
+        .inner
+        .create_block_with_transactions(signatures[quorum - 1].clone());
+    let tx = anchoring_testkit.last_anchoring_tx().unwrap();
+
+    // Signed size estimation should not underestimate the actual size.
+    let redeem_script = anchoring_testkit.actual_anchoring_config().redeem_script();
+    let estimated_vsize = tx.signed_vsize_estimate(&redeem_script);
+    let actual_vsize = (tx.0.get_weight() as u64 + 3) / 4;
+    assert!(estimated_vsize >= actual_vsize);
+    assert!(estimated_vsize - actual_vsize <= quorum as u64);
+}
+
+#[test]
+fn anchoring_quorum_single_node() {
+    test_anchoring_quorum(1);
+}
+
+#[test]
+fn anchoring_quorum_three_nodes() {
+    test_anchoring_quorum(3);
+}
+
+#[test]
+fn anchoring_quorum_seven_nodes() {
+    test_anchoring_quorum(7);
+}
+
+#[test]
+fn anchoring_quorum_ten_nodes() {
+    test_anchoring_quorum(10);
+}
+
 #[test]
 fn additional_funding() {
     let mut anchoring_testkit = AnchoringTestKit::default();
@@ -255,6 +329,99 @@ fn additional_funding() {
     assert_eq!(tx1.0.input[1].previous_output.txid, new_funding_tx.0.txid());
 }
 
+#[test]
+fn verify_anchoring_chain() {
+    let mut anchoring_testkit = AnchoringTestKit::default();
+    let anchoring_interval = anchoring_testkit
+        .actual_anchoring_config()
+        .anchoring_interval;
+
+    // Establish anchoring transactions chain with the initial funding transaction.
+    anchoring_testkit.inner.create_block_with_transactions(
+        anchoring_testkit
+            .create_signature_txs()
+            .into_iter()
+            .flatten(),
+    );
+    // Spend another funding transaction in the next anchoring transaction.
+    let (txs, _) = anchoring_testkit.create_funding_confirmation_txs(150_000);
+    anchoring_testkit.inner.create_block_with_transactions(txs);
+    anchoring_testkit
+        .inner
+        .create_blocks_until(Height(anchoring_interval));
+    anchoring_testkit.inner.create_block_with_transactions(
+        anchoring_testkit
+            .create_signature_txs()
+            .into_iter()
+            .flatten(),
+    );
+
+    let snapshot = anchoring_testkit.inner.snapshot();
+    let schema = get_anchoring_schema(&snapshot);
+    let transactions = schema.transactions_chain.iter().collect::<Vec<_>>();
+    assert_eq!(transactions.len(), 2);
+
+    let funding_transaction = |txid: &btc::Sha256d| schema.spent_funding_transaction(txid);
+    btc::verify_anchoring_chain(transactions.clone(), funding_transaction).unwrap();
+
+    // Funding transactions are unknown, all of them are reported.
+    let e = btc::verify_anchoring_chain(transactions.clone(), |_| None).unwrap_err();
+    assert_eq!(e.len(), 2);
+    assert!(matches!(
+        e[0],
+        btc::ChainError::UnknownInput {
+            index: 0,
+            input: 0,
+            ..
+        }
+    ));
+    assert!(matches!(
+        e[1],
+        btc::ChainError::UnknownInput {
+            index: 1,
+            input: 1,
+            ..
+        }
+    ));
+    // The second transaction does not spend the first one.
+    let first_tx = transactions[0].clone();
+    let e = btc::verify_anchoring_chain(vec![first_tx.clone(), first_tx], funding_transaction)
+        .unwrap_err();
+    assert!(matches!(e[0], btc::ChainError::BrokenLink { index: 1, .. }));
+    // The signature from another transaction does not match the spent output.
+    let mut forged_tx = transactions[0].clone();
+    forged_tx.0.input[0].witness[1] = transactions[1].0.input[0].witness[1].clone();
+    let e = btc::verify_anchoring_chain(
+        vec![forged_tx, transactions[1].clone()],
+        funding_transaction,
+    )
+    .unwrap_err();
+    assert_eq!(e.len(), 1);
+    assert!(matches!(
+        e[0],
+        btc::ChainError::InvalidWitness {
+            index: 0,
+            input: 0,
+            ..
+        }
+    ));
+    // Malformed transactions are reported instead of causing a panic.
+    let mut malformed_tx = transactions[1].clone();
+    malformed_tx.0.input.clear();
+    malformed_tx.0.output.clear();
+    let e = btc::verify_anchoring_chain(
+        vec![transactions[0].clone(), malformed_tx],
+        funding_transaction,
+    )
+    .unwrap_err();
+    assert!(e
+        .iter()
+        .any(|e| matches!(e, btc::ChainError::NoInputs { index: 1, .. })));
+    assert!(e
+        .iter()
+        .any(|e| matches!(e, btc::ChainError::NoOutputs { index: 1, .. })));
+}
+
 #[test]
 fn err_spent_funding() {
     let anchoring_interval = 5;